@@ -71,10 +71,10 @@ fn bench_unixiter(c: &mut Criterion) {
         "unix_pathiter",
         ParameterizedBenchmark::new(
             "manual",
-            |b, _| b.iter(|| simple_manual_unixiter()),
+            |b, _| b.iter(simple_manual_unixiter),
             vec![()],
         )
-        .with_function("parser", |b, _| b.iter(|| simple_parser_unixiter())),
+        .with_function("parser", |b, _| b.iter(simple_parser_unixiter)),
     );
 }
 
@@ -108,10 +108,10 @@ fn bench_winiter(c: &mut Criterion) {
         "win_pathiter",
         ParameterizedBenchmark::new(
             "manual",
-            |b, _| b.iter(|| simple_manual_winiter()),
+            |b, _| b.iter(simple_manual_winiter),
             vec![()],
         )
-        .with_function("parser", |b, _| b.iter(|| simple_parser_winiter())),
+        .with_function("parser", |b, _| b.iter(simple_parser_winiter)),
     );
 }
 
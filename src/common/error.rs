@@ -9,11 +9,14 @@
 
 // Stdlib imports
 pub use std::error::Error;
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::ops::Range;
 
 // Third-party imports
 
 // Local imports
+use crate::path::{SystemSeq, SystemStr};
 pub use crate::unix::UnixErrorKind;
 pub use crate::windows::WindowsErrorKind;
 
@@ -79,6 +82,37 @@ impl ParseError {
     pub fn kind(&self) -> ParseErrorKind {
         self._kind
     }
+
+    pub fn component(&self) -> &OsStr {
+        &self.component
+    }
+
+    pub fn path(&self) -> &OsStr {
+        &self.path
+    }
+
+    // Byte offset of the invalid component within path
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    pub fn span(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
+
+    // Displays the error like a compiler diagnostic, with the path on its
+    // own line and the invalid range underlined by carets
+    pub fn diagnostic(&self) -> Diagnostic<'_> {
+        Diagnostic { error: self }
+    }
 }
 
 impl Error for ParseError {
@@ -87,6 +121,126 @@ impl Error for ParseError {
     }
 }
 
+// Multi-line rendering of a ParseError, returned by
+// ParseError::diagnostic(). The carets line up with the invalid range
+// as long as each character of the path takes up one column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Diagnostic<'err> {
+    error: &'err ParseError,
+}
+
+impl<'err> fmt::Display for Diagnostic<'err> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let error = self.error;
+        let path = SystemStr::new(&error.path).as_bytes();
+        let end = error.end.min(path.len());
+        let start = error.start.min(end);
+
+        // Count characters rather than bytes so that multibyte characters
+        // before the range do not push the carets out of place
+        let column = String::from_utf8_lossy(&path[..start]).chars().count();
+        let width = String::from_utf8_lossy(&path[start..end]).chars().count();
+
+        writeln!(f, "error: {}", error.msg)?;
+        writeln!(f, "  |")?;
+        writeln!(f, "  | {}", String::from_utf8_lossy(path))?;
+        write!(f, "  | {}{}", " ".repeat(column), "^".repeat(width.max(1)))
+    }
+}
+
+// Why a path could not be made relative to a base path
+#[derive(Debug, Display, PartialEq, Eq)]
+pub enum RelativePathError {
+    #[display(fmt = "{}", _0)]
+    Parse(ParseError),
+
+    #[display(fmt = "one path is absolute and the other is relative")]
+    RootMismatch,
+
+    // Eg different drives or different UNC server/share
+    #[display(fmt = "paths have different prefixes")]
+    PrefixMismatch,
+
+    // The base has .. components after the part it shares with the path,
+    // so the names of the directories it climbs into are unknown
+    #[display(fmt = "base path climbs above the shared ancestor")]
+    UnknownBase,
+}
+
+impl From<ParseError> for RelativePathError {
+    fn from(error: ParseError) -> Self {
+        RelativePathError::Parse(error)
+    }
+}
+
+impl Error for RelativePathError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RelativePathError::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// Why the suffix of a file name could not be replaced
+#[derive(Debug, Display, PartialEq, Eq)]
+pub enum SuffixError {
+    #[display(fmt = "{}", _0)]
+    Parse(ParseError),
+
+    // Eg the path is / or ends in .., so there is no name to add it to
+    #[display(fmt = "path has no file name")]
+    NoFileName,
+}
+
+impl From<ParseError> for SuffixError {
+    fn from(error: ParseError) -> Self {
+        SuffixError::Parse(error)
+    }
+}
+
+impl Error for SuffixError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SuffixError::Parse(e) => Some(e),
+            SuffixError::NoFileName => None,
+        }
+    }
+}
+
+// Why a path could not be converted between Unix and Windows
+#[derive(Debug, Display, PartialEq, Eq)]
+pub enum ConvertError {
+    #[display(fmt = "{}", _0)]
+    Parse(ParseError),
+
+    // A component that the target cannot hold and the policy does not
+    // escape. The error has the kind of error the target would report, and
+    // the range of the component in the path being converted.
+    #[display(fmt = "{}", _0)]
+    InvalidName(ParseError),
+
+    // Eg a path on D: when the policy maps / to C:\, or a Unix root when
+    // the policy has an invalid drive or UNC share
+    #[display(fmt = "path root has no mapping in the conversion policy")]
+    UnmappedRoot,
+}
+
+impl From<ParseError> for ConvertError {
+    fn from(error: ParseError) -> Self {
+        ConvertError::Parse(error)
+    }
+}
+
+impl Error for ConvertError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConvertError::Parse(e) | ConvertError::InvalidName(e) => Some(e),
+            ConvertError::UnmappedRoot => None,
+        }
+    }
+}
+
 // ===========================================================================
 //
 // ===========================================================================
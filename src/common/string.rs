@@ -35,6 +35,15 @@ pub(crate) fn os_str_as_bytes(s: &OsStr) -> &[u8] {
     unsafe { &*(s as *const OsStr as *const [u8]) }
 }
 
+// Byte offset of inner within outer, where inner must be a slice of outer
+pub(crate) fn offset_in(outer: &[u8], inner: &[u8]) -> usize {
+    debug_assert!(
+        outer.as_ptr_range().contains(&inner.as_ptr())
+            || inner.as_ptr() == outer.as_ptr_range().end
+    );
+    inner.as_ptr() as usize - outer.as_ptr() as usize
+}
+
 pub(crate) fn ascii_uppercase(letter: u8) -> u8 {
     (letter as char).to_ascii_uppercase() as u8
 }
@@ -35,6 +35,10 @@ extern crate derive_more;
 // Re-exports
 // ===========================================================================
 
+pub use crate::common::error::{
+    ConvertError, Diagnostic, ParseError, ParseErrorKind, RelativePathError,
+    SuffixError,
+};
 pub use crate::common::AsSystemStr;
 pub use crate::path::{SystemStr, SystemString};
 
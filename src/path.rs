@@ -9,6 +9,7 @@
 
 // Stdlib imports
 use std::ffi::{OsStr, OsString};
+use std::fmt;
 use std::marker::PhantomData;
 use std::path::Path as StdPath;
 
@@ -61,6 +62,24 @@ pub trait PathIterator<'path>: Iterator {
         Self: Sized;
 }
 
+// Flavor specific rules for the items yielded by a PathIterator
+pub trait PathItem<'path> {
+    fn is_curdir(&self) -> bool;
+
+    // The component's name if it is a normal component
+    fn normal(&self) -> Option<&'path OsStr>;
+
+    fn is_root(&self) -> bool;
+
+    // Whether both items are valid and refer to the same component
+    fn same_component(&self, other: &Self) -> bool;
+
+    // Index of the dot that starts the last suffix of name, if name has one
+    fn suffix_start(name: &[u8]) -> Option<usize>;
+
+    fn is_separator(byte: u8) -> bool;
+}
+
 pub trait Path<'path, I>: AsSystemStr
 where
     I: PathIterator<'path>,
@@ -72,9 +91,138 @@ where
     // --------------------
     // Properties
     // --------------------
-    fn parts(&'path self) -> PathParts<I> {
+    fn parts(&'path self) -> PathParts<'path, I> {
         PathParts::new(self.iter())
     }
+
+    // The last component if it is a normal component, ignoring any trailing
+    // CurDir components
+    fn file_name(&'path self) -> Option<&'path OsStr>
+    where
+        I::Item: PathItem<'path>,
+    {
+        let last = self.iter().filter(|c| !c.is_curdir()).last()?;
+        last.normal()
+    }
+
+    fn file_stem(&'path self) -> Option<&'path OsStr>
+    where
+        I::Item: PathItem<'path>,
+    {
+        let name = SystemStr::new(self.file_name()?).as_bytes();
+        let stem = match I::Item::suffix_start(name) {
+            Some(i) => &name[..i],
+            None => name,
+        };
+        Some(as_osstr(stem))
+    }
+
+    // The last suffix of the file name without its leading dot
+    fn extension(&'path self) -> Option<&'path OsStr>
+    where
+        I::Item: PathItem<'path>,
+    {
+        let name = SystemStr::new(self.file_name()?).as_bytes();
+        let start = I::Item::suffix_start(name)?;
+        Some(as_osstr(&name[start + 1..]))
+    }
+
+    // All suffixes of the file name in order, each with its leading dot
+    fn suffixes(&'path self) -> Vec<&'path OsStr>
+    where
+        I::Item: PathItem<'path>,
+    {
+        let mut suffixes = Vec::new();
+        if let Some(name) = self.file_name() {
+            let mut stem = SystemStr::new(name).as_bytes();
+            while let Some(i) = I::Item::suffix_start(stem) {
+                suffixes.push(as_osstr(&stem[i..]));
+                stem = &stem[..i];
+            }
+            suffixes.reverse();
+        }
+        suffixes
+    }
+
+    // --------------------
+    // Comparison
+    // --------------------
+
+    // Paths are compared by component rather than by byte, and CurDir
+    // components are ignored, so a//b and a/./b both start with a/b but
+    // /a/bc does not start with /a/b
+    fn starts_with<B: AsRef<OsStr> + ?Sized>(
+        &'path self,
+        base: &'path B,
+    ) -> bool
+    where
+        I: AsRef<SystemStr>,
+        I::Item: PathItem<'path>,
+    {
+        self.strip_prefix(base).is_some()
+    }
+
+    fn ends_with<C: AsRef<OsStr> + ?Sized>(&'path self, child: &'path C) -> bool
+    where
+        I::Item: PathItem<'path>,
+    {
+        let path: Vec<I::Item> =
+            self.iter().filter(|c| !c.is_curdir()).collect();
+        let child: Vec<I::Item> = I::new(SystemStr::new(child))
+            .filter(|c| !c.is_curdir())
+            .collect();
+
+        child.len() <= path.len()
+            && path[path.len() - child.len()..]
+                .iter()
+                .zip(&child)
+                .all(|(a, b)| a.same_component(b))
+    }
+
+    // The rest of the path once the components of base are removed from its
+    // start, or None if the path does not start with base
+    fn strip_prefix<B: AsRef<OsStr> + ?Sized>(
+        &'path self,
+        base: &'path B,
+    ) -> Option<&'path OsStr>
+    where
+        I: AsRef<SystemStr>,
+        I::Item: PathItem<'path>,
+    {
+        let path = self.as_sys_str().as_bytes();
+        let mut iter = self.iter();
+        for base_comp in I::new(SystemStr::new(base)) {
+            if base_comp.is_curdir() {
+                continue;
+            }
+            let comp = iter.by_ref().find(|c| !c.is_curdir())?;
+            if !comp.same_component(&base_comp) {
+                return None;
+            }
+        }
+
+        // The rest starts at the next component that is not a CurDir. Any
+        // separators before it are dropped unless it is a root.
+        let mut start;
+        loop {
+            let rest: &SystemStr = iter.as_ref();
+            start = path.len() - rest.as_bytes().len();
+            match iter.next() {
+                Some(ref c) if c.is_curdir() => continue,
+                Some(ref c) if !c.is_root() => {
+                    while start < path.len()
+                        && I::Item::is_separator(path[start])
+                    {
+                        start += 1;
+                    }
+                }
+                Some(_) => {}
+                None => start = path.len(),
+            }
+            break;
+        }
+        Some(as_osstr(&path[start..]))
+    }
 }
 
 pub trait PathBuf<'path, I>: Path<'path, I>
@@ -164,7 +312,7 @@ impl SystemStr {
 impl SystemSeq for SystemStr {
     #[cfg(unix)]
     fn as_bytes(&self) -> &[u8] {
-        (&self.inner).as_bytes()
+        self.inner.as_bytes()
     }
 
     #[cfg(windows)]
@@ -227,6 +375,23 @@ impl SystemString {
         Default::default()
     }
 
+    pub fn with_capacity(capacity: usize) -> SystemString {
+        let inner = OsString::with_capacity(capacity);
+        SystemString { inner }
+    }
+
+    pub fn push<P>(&mut self, s: &P)
+    where
+        P: AsRef<OsStr> + ?Sized,
+    {
+        self.inner.push(s);
+    }
+
+    pub(crate) fn truncate(&mut self, len: usize) {
+        let inner = as_osstr(&self.as_bytes()[..len]).to_os_string();
+        self.inner = inner;
+    }
+
     pub fn from_bytes<P>(p: &P) -> SystemString
     where
         P: AsRef<[u8]> + ?Sized,
@@ -309,6 +474,52 @@ impl AsRef<OsStr> for SystemString {
 
 path_asref_impl!(StdPath, SystemString);
 
+// ===========================================================================
+// SeparatorDisplay
+// ===========================================================================
+
+// Shows a path with every separator written as the same character, eg
+// C:/dir\file as C:\dir\file. This is the mode that normalizes mixed
+// separators to a single style: WindowsPath::as_posix() writes them all as
+// / and WindowsPath::as_windows() as \. UnixPath::as_windows() returns one
+// too. Names are written as they are, with invalid UTF-8 replaced.
+#[derive(Debug, Clone, Copy)]
+pub struct SeparatorDisplay<'path> {
+    path: &'path [u8],
+    is_separator: fn(u8) -> bool,
+    separator: u8,
+}
+
+impl<'path> SeparatorDisplay<'path> {
+    pub(crate) fn new(
+        path: &'path [u8],
+        is_separator: fn(u8) -> bool,
+        separator: u8,
+    ) -> Self {
+        SeparatorDisplay {
+            path,
+            is_separator,
+            separator,
+        }
+    }
+}
+
+impl<'path> fmt::Display for SeparatorDisplay<'path> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Separators are ascii, so splitting on them never splits a UTF-8
+        // sequence in two
+        let mut names = self.path.split(|&b| (self.is_separator)(b));
+        if let Some(first) = names.next() {
+            f.write_str(&String::from_utf8_lossy(first))?;
+        }
+        for name in names {
+            write!(f, "{}", self.separator as char)?;
+            f.write_str(&String::from_utf8_lossy(name))?;
+        }
+        Ok(())
+    }
+}
+
 // ===========================================================================
 //
 // ===========================================================================
@@ -21,7 +21,6 @@ use std::ffi::OsStr;
 // Local imports
 use crate::common::{string::as_str, AsSystemStr, PathData};
 use crate::path::{PathIterator, SystemStr};
-use crate::pathiter_trait_impl;
 use crate::{unix, windows};
 
 // ===========================================================================
@@ -74,7 +73,7 @@ struct UnixPathIterBuilder;
 
 struct WindowsPathIterBuilder;
 
-impl<'path> PathIterBuilder for TestPathIterBuilder {
+impl PathIterBuilder for TestPathIterBuilder {
     fn build(
         &self,
         path: &'static [u8],
@@ -86,7 +85,7 @@ impl<'path> PathIterBuilder for TestPathIterBuilder {
     }
 }
 
-impl<'path> PathIterBuilder for UnixPathIterBuilder {
+impl PathIterBuilder for UnixPathIterBuilder {
     fn build(
         &self,
         path: &'static [u8],
@@ -108,7 +107,7 @@ impl<'path> PathIterBuilder for UnixPathIterBuilder {
     }
 }
 
-impl<'path> PathIterBuilder for WindowsPathIterBuilder {
+impl PathIterBuilder for WindowsPathIterBuilder {
     fn build(
         &self,
         path: &'static [u8],
@@ -136,7 +135,7 @@ struct UnixCompBuilder;
 
 struct WindowsCompBuilder;
 
-impl<'path> CompBuilder for TestCompBuilder {
+impl CompBuilder for TestCompBuilder {
     fn build_osstr(&self, path: &'static OsStr) -> Box<dyn AsRef<OsStr>> {
         build_component_struct!();
 
@@ -150,9 +149,9 @@ impl<'path> CompBuilder for TestCompBuilder {
     }
 }
 
-impl<'path> CompBuilder for UnixCompBuilder {
+impl CompBuilder for UnixCompBuilder {
     fn build_osstr(&self, path: &'static OsStr) -> Box<dyn AsRef<OsStr>> {
-        return Box::new(unix::Component::Normal(path));
+        Box::new(unix::Component::Normal(path))
     }
 
     fn build_path(&self, path: &'static OsStr) -> Box<dyn AsRef<SystemStr>> {
@@ -160,7 +159,7 @@ impl<'path> CompBuilder for UnixCompBuilder {
     }
 }
 
-impl<'path> CompBuilder for WindowsCompBuilder {
+impl CompBuilder for WindowsCompBuilder {
     fn build_osstr(&self, path: &'static OsStr) -> Box<dyn AsRef<OsStr>> {
         Box::new(windows::Component::Normal(path))
     }
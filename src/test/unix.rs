@@ -15,15 +15,367 @@ use std::ffi::{OsStr, OsString};
 
 // Local imports
 use crate::path::{PathIterator, SystemStr};
-use crate::unix::{Component, Iter, PathComponent};
+use crate::unix::{Component, Iter, PathComponent, Token, Tokens, UnixPath};
 
 // ===========================================================================
 // Tests
 // ===========================================================================
 
+mod unixpath {
+    use crate::common::error::*;
+    use crate::path::SystemSeq;
+    use crate::unix::{UnixPath, UnixPathBuf};
+    use crate::windows::{ConvertPolicy, OnInvalid, RootMap, WindowsPathBuf};
+    use std::ffi::OsStr;
+
+    fn parent(path: &str) -> Option<&OsStr> {
+        UnixPath::new(path).parent().map(|p| p.as_os_str())
+    }
+
+    #[test]
+    fn parent_absolute() {
+        assert_eq!(parent("/hello/world"), Some(OsStr::new("/hello")));
+        assert_eq!(parent("/hello//world/"), Some(OsStr::new("/hello")));
+        assert_eq!(parent("/hello"), Some(OsStr::new("/")));
+        assert_eq!(parent("/"), None);
+    }
+
+    #[test]
+    fn parent_relative() {
+        assert_eq!(parent("hello/world"), Some(OsStr::new("hello")));
+        assert_eq!(parent("hello/.."), Some(OsStr::new("hello")));
+        assert_eq!(parent("hello"), Some(OsStr::new("")));
+        assert_eq!(parent(""), None);
+    }
+
+    #[test]
+    fn ancestors() {
+        let path = UnixPath::new("/hello/world/now");
+        let ancestors: Vec<&OsStr> =
+            path.ancestors().map(|p| p.as_os_str()).collect();
+        let expected: Vec<&OsStr> =
+            ["/hello/world/now", "/hello/world", "/hello", "/"]
+                .iter()
+                .map(OsStr::new)
+                .collect();
+        assert_eq!(ancestors, expected);
+
+        let path = UnixPathBuf::from("hello/world");
+        let ancestors: Vec<&OsStr> =
+            path.ancestors().map(|p| p.as_os_str()).collect();
+        let expected: Vec<&OsStr> = ["hello/world", "hello", ""]
+            .iter()
+            .map(OsStr::new)
+            .collect();
+        assert_eq!(ancestors, expected);
+    }
+
+    fn normalize(path: &str) -> UnixPathBuf {
+        UnixPath::new(path).normalize().unwrap()
+    }
+
+    #[test]
+    fn normalize_absolute() {
+        for (path, expected) in &[
+            ("/hello/world", "/hello/world"),
+            ("/hello//./world/", "/hello/world"),
+            ("/hello/../world", "/world"),
+            ("/hello/what/../../world/now/..", "/world"),
+            ("/../hello", "/hello"),
+            ("/..", "/"),
+            ("//", "/"),
+        ] {
+            assert_eq!(normalize(path).as_os_str(), OsStr::new(expected));
+        }
+    }
+
+    #[test]
+    fn normalize_relative() {
+        for (path, expected) in &[
+            ("hello/./world", "hello/world"),
+            ("hello/..", "."),
+            ("", "."),
+            ("./", "."),
+            ("../hello/../..", "../.."),
+            ("hello/../../world", "../world"),
+        ] {
+            assert_eq!(normalize(path).as_os_str(), OsStr::new(expected));
+        }
+    }
+
+    #[test]
+    fn normalize_invalid() {
+        let path = UnixPathBuf::from("/hello/wor\x00ld");
+        let err = path.normalize().unwrap_err();
+        assert_eq!(err.kind(), UnixErrorKind::InvalidCharacter.into());
+    }
+
+    #[test]
+    fn clean() {
+        let path = UnixPathBuf::from("hello//world/../now");
+        assert_eq!(path.clean().unwrap(), UnixPathBuf::from("hello/now"));
+    }
+
+    fn relative_to(
+        path: &str,
+        base: &str,
+    ) -> Result<UnixPathBuf, RelativePathError> {
+        UnixPath::new(path).relative_to(base)
+    }
+
+    #[test]
+    fn relative_to_absolute() {
+        for (path, base, expected) in &[
+            ("/a/b/x/y", "/a/b", "x/y"),
+            ("/a/b", "/a/b/c/d", "../.."),
+            ("/a/x/y", "/a/b/c", "../../x/y"),
+            ("/a/b", "/a/b", "."),
+            ("/x", "/", "x"),
+            ("/a/./b/../c", "/a//d/", "../c"),
+        ] {
+            let relative = relative_to(path, base).unwrap();
+            assert_eq!(relative.as_os_str(), OsStr::new(expected));
+        }
+    }
+
+    #[test]
+    fn relative_to_relative() {
+        for (path, base, expected) in &[
+            ("a/x", "a/b", "../x"),
+            ("../x", "../b/c", "../../x"),
+            ("x", "", "x"),
+            ("..", "a", "../.."),
+        ] {
+            let relative = relative_to(path, base).unwrap();
+            assert_eq!(relative.as_os_str(), OsStr::new(expected));
+        }
+    }
+
+    #[test]
+    fn relative_to_error() {
+        let err = relative_to("/a/b", "a").unwrap_err();
+        assert_eq!(err, RelativePathError::RootMismatch);
+
+        let err = relative_to("a", "/a").unwrap_err();
+        assert_eq!(err, RelativePathError::RootMismatch);
+
+        let err = relative_to("a", "../b").unwrap_err();
+        assert_eq!(err, RelativePathError::UnknownBase);
+
+        let err = relative_to("/a\x00", "/a").unwrap_err();
+        match err {
+            RelativePathError::Parse(e) => {
+                assert_eq!(e.kind(), UnixErrorKind::InvalidCharacter.into())
+            }
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    fn escape_all() -> ConvertPolicy {
+        ConvertPolicy::new()
+            .on_invalid_char(OnInvalid::Escape)
+            .on_reserved_name(OnInvalid::Escape)
+    }
+
+    #[test]
+    fn as_windows() {
+        let shown = UnixPath::new("/usr/lib/x.so").as_windows().to_string();
+        assert_eq!(shown, r"\usr\lib\x.so");
+        let shown = UnixPath::new("a//b/").as_windows().to_string();
+        assert_eq!(shown, r"a\\b\");
+
+        let pathbuf = UnixPathBuf::from("build/out");
+        assert_eq!(pathbuf.as_windows().to_string(), r"build\out");
+    }
+
+    #[test]
+    fn to_windows_roots() {
+        let path = UnixPath::new("/src/./lib/../main.rs");
+        let rooted = path.to_windows(&ConvertPolicy::new()).unwrap();
+        assert_eq!(rooted, WindowsPathBuf::from(r"\src\.\lib\..\main.rs"));
+
+        let drive = ConvertPolicy::new().with_root(RootMap::Drive(b'c'));
+        let converted = UnixPath::new("/src/main.rs").to_windows(&drive);
+        assert_eq!(converted, Ok(WindowsPathBuf::from(r"C:\src\main.rs")));
+        let converted = UnixPath::new("/").to_windows(&drive);
+        assert_eq!(converted, Ok(WindowsPathBuf::from(r"C:\")));
+        let converted = UnixPath::new("src/main.rs").to_windows(&drive);
+        assert_eq!(converted, Ok(WindowsPathBuf::from(r"src\main.rs")));
+
+        // Repeated separators are collapsed, but an explicit . is kept
+        for (unix, windows) in &[
+            ("a//b", r"a\b"),
+            ("//a/b", r"C:\a\b"),
+            ("/a///b//", r"C:\a\b"),
+            ("./a//./b", r".\a\.\b"),
+        ] {
+            let converted = UnixPath::new(unix).to_windows(&drive);
+            assert_eq!(converted, Ok(WindowsPathBuf::from(*windows)));
+        }
+        let converted =
+            UnixPath::new("//a/b").to_windows(&ConvertPolicy::new());
+        assert_eq!(converted, Ok(WindowsPathBuf::from(r"\a\b")));
+
+        let unc = ConvertPolicy::new().with_root(RootMap::Unc {
+            server: "build".into(),
+            share: "out".into(),
+        });
+        let converted = UnixPath::new("/bin/app").to_windows(&unc);
+        assert_eq!(converted, Ok(WindowsPathBuf::from(r"\\build\out\bin\app")));
+    }
+
+    #[test]
+    fn to_windows_unmapped_root() {
+        let policy = ConvertPolicy::new().with_root(RootMap::Drive(b'1'));
+        let err = UnixPath::new("/a").to_windows(&policy).unwrap_err();
+        assert_eq!(err, ConvertError::UnmappedRoot);
+
+        let policy = ConvertPolicy::new().with_root(RootMap::Unc {
+            server: "bad host".into(),
+            share: "out".into(),
+        });
+        let err = UnixPath::new("/a").to_windows(&policy).unwrap_err();
+        assert_eq!(err, ConvertError::UnmappedRoot);
+
+        // Relative paths never need the root
+        assert!(UnixPath::new("a").to_windows(&policy).is_ok());
+    }
+
+    #[test]
+    fn to_windows_invalid_names() {
+        let path = UnixPath::new("/docs/what?/nul.txt");
+        let err = path.to_windows(&ConvertPolicy::new()).unwrap_err();
+        match err {
+            ConvertError::InvalidName(e) => {
+                assert_eq!(e.kind(), WindowsErrorKind::InvalidCharacter.into());
+                assert_eq!(e.span(), 6..11);
+                assert_eq!(e.component(), OsStr::new("what?"));
+            }
+            _ => panic!("expected an invalid name"),
+        }
+
+        let policy = ConvertPolicy::new().on_invalid_char(OnInvalid::Escape);
+        let err = path.to_windows(&policy).unwrap_err();
+        match err {
+            ConvertError::InvalidName(e) => {
+                assert_eq!(e.kind(), WindowsErrorKind::RestrictedName.into());
+                assert_eq!(e.span(), 12..19);
+            }
+            _ => panic!("expected a reserved name"),
+        }
+
+        let converted = path.to_windows(&escape_all()).unwrap();
+        assert_eq!(converted, WindowsPathBuf::from(r"\docs\what%3F\%6Eul.txt"));
+        assert!(converted.validate_all().is_empty());
+        assert!(!converted.is_reserved());
+    }
+
+    #[test]
+    fn to_windows_parse_error() {
+        let err = UnixPath::new("/a\x00").to_windows(&ConvertPolicy::new());
+        match err.unwrap_err() {
+            ConvertError::Parse(e) => {
+                assert_eq!(e.kind(), UnixErrorKind::InvalidCharacter.into())
+            }
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn to_windows_round_trip() {
+        let policy = escape_all().with_root(RootMap::Drive(b'C'));
+        let paths = [
+            "/home/user/a:b/c\\d/100%/trailing./COM1",
+            "../x/ spaced /aux",
+            "",
+        ];
+        for path in paths.iter() {
+            let path = UnixPath::new(path);
+            let windows = path.to_windows(&policy).unwrap();
+            assert!(windows.validate_all().is_empty(), "{:?}", windows);
+            assert_eq!(windows.to_unix(&policy).unwrap().as_path(), path);
+        }
+    }
+
+    #[test]
+    fn to_windows_mixed_policy() {
+        let drive = ConvertPolicy::new().with_root(RootMap::Drive(b'C'));
+        let policies = vec![
+            drive.clone().on_reserved_name(OnInvalid::Escape),
+            drive
+                .clone()
+                .on_invalid_char(OnInvalid::PrivateUse)
+                .on_reserved_name(OnInvalid::Escape),
+        ];
+        for policy in policies {
+            let path = UnixPath::new("/nul");
+            let windows = path.to_windows(&policy).unwrap();
+            assert_eq!(windows, WindowsPathBuf::from(r"C:\%6Eul"));
+            assert_eq!(windows.to_unix(&policy).unwrap().as_path(), path);
+
+            let path = UnixPath::new("/%6Eul/100%");
+            let windows = path.to_windows(&policy).unwrap();
+            assert_eq!(windows, WindowsPathBuf::from(r"C:\%256Eul\100%"));
+            assert_eq!(windows.to_unix(&policy).unwrap().as_path(), path);
+        }
+    }
+
+    #[test]
+    fn to_windows_private_use() {
+        let policy = ConvertPolicy::new()
+            .with_root(RootMap::Drive(b'C'))
+            .on_invalid_char(OnInvalid::PrivateUse)
+            .on_reserved_name(OnInvalid::PrivateUse);
+        let path = UnixPath::new("/tmp/a:b/what?/trailing./100%/nul");
+        let windows = path.to_windows(&policy).unwrap();
+        let expected = "C:\\tmp\\a\u{f03a}b\\what\u{f03f}\\trailing\u{f02e}\\100%\\\u{f06e}ul";
+        assert_eq!(windows, WindowsPathBuf::from(expected));
+        assert!(windows.validate_all().is_empty());
+        assert_eq!(windows.to_unix(&policy).unwrap().as_path(), path);
+    }
+
+    #[test]
+    fn pathbuf_parent() {
+        let path = UnixPathBuf::from("/hello/world");
+        let relative = path.relative_to("/hello/now").unwrap();
+        assert_eq!(relative, UnixPathBuf::from("../world"));
+        assert_eq!(path.parent(), Some(UnixPath::new("/hello")));
+        assert_eq!(path.as_path(), UnixPath::new("/hello/world"));
+    }
+
+    #[test]
+    fn validate_all_clean() {
+        assert!(UnixPath::new("/hello/./world/../now/")
+            .validate_all()
+            .is_empty());
+        assert!(UnixPath::new("").validate_all().is_empty());
+    }
+
+    #[test]
+    fn validate_all() {
+        let path = UnixPath::new("/hel\u{0}lo/world/wh\u{0}at");
+        let errors = path.validate_all();
+
+        assert_eq!(errors.len(), 2);
+        for e in &errors {
+            assert_eq!(
+                e.kind(),
+                ParseErrorKind::Unix(UnixErrorKind::InvalidCharacter)
+            );
+        }
+        assert!(errors[0].to_string().contains("range 1..7"));
+        assert!(errors[1].to_string().contains("range 14..19"));
+
+        let pathbuf = UnixPathBuf::from(path.as_os_str());
+        assert_eq!(pathbuf.validate_all(), errors);
+    }
+}
+
 mod unixpathbuf {
+    use crate::common::error::*;
     use crate::path::{SystemSeq, SystemString};
-    use crate::unix::UnixPathBuf;
+    use crate::unix::{UnixPath, UnixPathBuf};
+    use std::ffi::OsStr;
 
     #[test]
     fn deref_to_systemstring() {
@@ -31,6 +383,193 @@ mod unixpathbuf {
         let inner: &SystemString = &path;
         assert_eq!(inner.as_os_str().len(), 0);
     }
+
+    #[test]
+    fn push_relative() {
+        let mut path = UnixPathBuf::from("/hello");
+        path.push("world");
+        assert_eq!(path.as_os_str(), OsStr::new("/hello/world"));
+
+        let mut path = UnixPathBuf::from("/hello/");
+        path.push("world/now");
+        assert_eq!(path.as_os_str(), OsStr::new("/hello/world/now"));
+    }
+
+    #[test]
+    fn push_onto_empty() {
+        let mut path = UnixPathBuf::new();
+        path.push("hello");
+        assert_eq!(path.as_os_str(), OsStr::new("hello"));
+    }
+
+    #[test]
+    fn push_absolute_replaces() {
+        let mut path = UnixPathBuf::from("/hello/world");
+        path.push("/what/now");
+        assert_eq!(path.as_os_str(), OsStr::new("/what/now"));
+    }
+
+    #[test]
+    fn pop() {
+        let mut path = UnixPathBuf::from("/hello//world/");
+        assert!(path.pop());
+        assert_eq!(path.as_os_str(), OsStr::new("/hello"));
+
+        assert!(path.pop());
+        assert_eq!(path.as_os_str(), OsStr::new("/"));
+
+        assert!(!path.pop());
+        assert_eq!(path.as_os_str(), OsStr::new("/"));
+    }
+
+    #[test]
+    fn pop_relative() {
+        let mut path = UnixPathBuf::from("hello/./world");
+        assert!(path.pop());
+        assert_eq!(path.as_os_str(), OsStr::new("hello/."));
+
+        assert!(path.pop());
+        assert!(path.pop());
+        assert_eq!(path.as_os_str(), OsStr::new(""));
+
+        assert!(!path.pop());
+    }
+
+    #[test]
+    fn join() {
+        let path = UnixPath::new("/hello");
+        let joined = path.join("world");
+        assert_eq!(joined.as_os_str(), OsStr::new("/hello/world"));
+
+        let joined = joined.join("/what");
+        assert_eq!(joined.as_os_str(), OsStr::new("/what"));
+    }
+
+    #[test]
+    fn with_capacity() {
+        let path = UnixPathBuf::with_capacity(10);
+        assert_eq!(path, UnixPathBuf::new());
+    }
+
+    #[test]
+    fn set_file_name() {
+        let mut path = UnixPathBuf::from("/hello/world.txt");
+        path.set_file_name("what.rs").unwrap();
+        assert_eq!(path.as_os_str(), OsStr::new("/hello/what.rs"));
+
+        // Trailing separators and curdir after the file name are dropped
+        let mut path = UnixPathBuf::from("hello/world/./");
+        path.set_file_name("now").unwrap();
+        assert_eq!(path.as_os_str(), OsStr::new("hello/now"));
+    }
+
+    #[test]
+    fn set_file_name_no_file_name() {
+        let mut path = UnixPathBuf::from("/");
+        path.set_file_name("hello").unwrap();
+        assert_eq!(path.as_os_str(), OsStr::new("/hello"));
+
+        let mut path = UnixPathBuf::from("hello/..");
+        path.set_file_name("world").unwrap();
+        assert_eq!(path.as_os_str(), OsStr::new("hello/../world"));
+    }
+
+    #[test]
+    fn set_file_name_invalid() {
+        let mut path = UnixPathBuf::from("/hello/world");
+        for (name, kind) in &[
+            ("what\x00", UnixErrorKind::InvalidCharacter),
+            ("what/now", UnixErrorKind::InvalidCharacter),
+            ("", UnixErrorKind::RestrictedName),
+            (".", UnixErrorKind::RestrictedName),
+            ("..", UnixErrorKind::RestrictedName),
+        ] {
+            let err = path.set_file_name(*name).unwrap_err();
+            assert_eq!(err.kind(), ParseErrorKind::Unix(*kind));
+        }
+        assert_eq!(path.as_os_str(), OsStr::new("/hello/world"));
+    }
+
+    #[test]
+    fn set_extension() {
+        let mut path = UnixPathBuf::from("/hello/world.tar.gz");
+        assert!(path.set_extension("xz").unwrap());
+        assert_eq!(path.as_os_str(), OsStr::new("/hello/world.tar.xz"));
+
+        assert!(path.set_extension("").unwrap());
+        assert_eq!(path.as_os_str(), OsStr::new("/hello/world.tar"));
+
+        let mut path = UnixPathBuf::from("/hello/.bashrc");
+        assert!(path.set_extension("bak").unwrap());
+        assert_eq!(path.as_os_str(), OsStr::new("/hello/.bashrc.bak"));
+
+        let mut path = UnixPathBuf::from("/");
+        assert!(!path.set_extension("txt").unwrap());
+        assert_eq!(path.as_os_str(), OsStr::new("/"));
+
+        let mut path = UnixPathBuf::from("/hello/world");
+        assert!(path.set_extension("a/b").is_err());
+    }
+
+    #[test]
+    fn with_name() {
+        let path = UnixPathBuf::from("/hello/world.txt");
+        let renamed = path.with_name("now.rs").unwrap();
+        assert_eq!(renamed.as_os_str(), OsStr::new("/hello/now.rs"));
+        assert_eq!(path.as_os_str(), OsStr::new("/hello/world.txt"));
+
+        let err = path.with_name("now\x00").unwrap_err();
+        assert_eq!(err.kind(), UnixErrorKind::InvalidCharacter.into());
+    }
+
+    #[test]
+    fn with_stem() {
+        let path = UnixPathBuf::from("/hello/world.tar.gz");
+        let renamed = path.with_stem("now").unwrap();
+        assert_eq!(renamed.as_os_str(), OsStr::new("/hello/now.gz"));
+
+        let path = UnixPathBuf::from("/hello/world");
+        let renamed = path.with_stem("now").unwrap();
+        assert_eq!(renamed.as_os_str(), OsStr::new("/hello/now"));
+    }
+
+    #[test]
+    fn with_suffix() {
+        let path = UnixPathBuf::from("/hello/world.txt");
+        let renamed = path.with_suffix(".rs").unwrap();
+        assert_eq!(renamed.as_os_str(), OsStr::new("/hello/world.rs"));
+
+        let renamed = path.with_suffix("").unwrap();
+        assert_eq!(renamed.as_os_str(), OsStr::new("/hello/world"));
+
+        let renamed = path.with_suffix(".tar.gz").unwrap();
+        assert_eq!(renamed.as_os_str(), OsStr::new("/hello/world.tar.gz"));
+
+        let renamed = path.with_suffix(". x.").unwrap();
+        assert_eq!(renamed.as_os_str(), OsStr::new("/hello/world. x."));
+    }
+
+    #[test]
+    fn with_suffix_invalid() {
+        let path = UnixPathBuf::from("/hello/world.txt");
+        for suffix in &["rs", ".", ".a/b", ".a\x00"] {
+            match path.with_suffix(*suffix).unwrap_err() {
+                SuffixError::Parse(e) => {
+                    assert_eq!(e.kind(), UnixErrorKind::InvalidCharacter.into())
+                }
+                err => panic!("unexpected error: {:?}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn with_suffix_no_file_name() {
+        for path in &["/", "", "/hello/.."] {
+            let path = UnixPathBuf::from(*path);
+            let err = path.with_suffix(".rs").unwrap_err();
+            assert_eq!(err, SuffixError::NoFileName);
+        }
+    }
 }
 
 mod public_export {
@@ -67,6 +606,22 @@ mod public_export {
 
             assert_eq!(err.kind(), UnixErrorKind::InvalidCharacter.into());
         }
+
+        #[test]
+        fn diagnostic_multibyte() {
+            let err = ParseError::new(
+                UnixErrorKind::InvalidCharacter.into(),
+                OsString::from("老虎\u{0}"),
+                OsString::from("/Löwe/老虎\u{0}"),
+                7,
+                14,
+                String::from("path component contains an invalid character"),
+            );
+            let rendered = err.diagnostic().to_string();
+
+            let carets = rendered.lines().last().unwrap();
+            assert_eq!(carets, "  |       ^^^");
+        }
     }
 
     mod component {
@@ -150,21 +705,21 @@ mod iter {
     use super::*;
 
     #[test]
-    fn prefix_noroot<'path>() {
+    fn prefix_noroot() {
         let path = b"hello";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 1);
 
-        let expected: Vec<PathComponent<'path>> =
+        let expected: Vec<PathComponent> =
             vec![Ok(Component::Normal(OsStr::new("hello")))];
 
         assert_eq!(comp, expected);
     }
 
     #[test]
-    fn invalid_char<'path>() {
+    fn invalid_char() {
         let path = b"/hello\x00/world";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
@@ -172,32 +727,31 @@ mod iter {
 
         assert_eq!(comp.len(), 2);
 
-        let expected_ok: Vec<PathComponent<'path>> =
-            vec![Ok(Component::RootDir)];
+        let expected_ok: Vec<PathComponent> = vec![Ok(Component::RootDir)];
 
         assert_eq!(&comp[..1], &expected_ok[..]);
 
         // Check last element is an error
         let result = match &comp[1] {
             Ok(_) => false,
-            Err(e) => match e.kind() {
-                ParseErrorKind::Unix(UnixErrorKind::InvalidCharacter) => true,
-                _ => false,
-            },
+            Err(e) => matches!(
+                e.kind(),
+                ParseErrorKind::Unix(UnixErrorKind::InvalidCharacter)
+            ),
         };
 
         assert!(result);
     }
 
     #[test]
-    fn relative_path<'path>() {
+    fn relative_path() {
         let path = b"hello/world";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 2);
 
-        let expected: Vec<PathComponent<'path>> = vec![
+        let expected: Vec<PathComponent> = vec![
             Ok(Component::Normal(OsStr::new(r"hello"))),
             Ok(Component::Normal(OsStr::new(r"world"))),
         ];
@@ -206,14 +760,14 @@ mod iter {
     }
 
     #[test]
-    fn double_path_separator<'path>() {
+    fn double_path_separator() {
         let path = br"hello//world";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 3);
 
-        let expected: Vec<PathComponent<'path>> = vec![
+        let expected: Vec<PathComponent> = vec![
             Ok(Component::Normal(OsStr::new(r"hello"))),
             Ok(Component::CurDir),
             Ok(Component::Normal(OsStr::new(r"world"))),
@@ -223,14 +777,14 @@ mod iter {
     }
 
     #[test]
-    fn curdir<'path>() {
+    fn curdir() {
         let path = br"hello/world/./what/now";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 5);
 
-        let expected: Vec<PathComponent<'path>> = vec![
+        let expected: Vec<PathComponent> = vec![
             Ok(Component::Normal(OsStr::new(r"hello"))),
             Ok(Component::Normal(OsStr::new(r"world"))),
             Ok(Component::CurDir),
@@ -242,14 +796,14 @@ mod iter {
     }
 
     #[test]
-    fn parentdir<'path>() {
+    fn parentdir() {
         let path = br"hello/world/../what/now";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 5);
 
-        let expected: Vec<PathComponent<'path>> = vec![
+        let expected: Vec<PathComponent> = vec![
             Ok(Component::Normal(OsStr::new(r"hello"))),
             Ok(Component::Normal(OsStr::new(r"world"))),
             Ok(Component::ParentDir),
@@ -261,14 +815,14 @@ mod iter {
     }
 
     #[test]
-    fn curdir_at_start<'path>() {
+    fn curdir_at_start() {
         let path = br"./hello/world";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 3);
 
-        let expected: Vec<PathComponent<'path>> = vec![
+        let expected: Vec<PathComponent> = vec![
             Ok(Component::CurDir),
             Ok(Component::Normal(OsStr::new(r"hello"))),
             Ok(Component::Normal(OsStr::new(r"world"))),
@@ -278,14 +832,14 @@ mod iter {
     }
 
     #[test]
-    fn parentdir_at_start<'path>() {
+    fn parentdir_at_start() {
         let path = br"../hello/world/";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 3);
 
-        let expected: Vec<PathComponent<'path>> = vec![
+        let expected: Vec<PathComponent> = vec![
             Ok(Component::ParentDir),
             Ok(Component::Normal(OsStr::new(r"hello"))),
             Ok(Component::Normal(OsStr::new(r"world"))),
@@ -295,14 +849,14 @@ mod iter {
     }
 
     #[test]
-    fn absolute_path<'path>() {
+    fn absolute_path() {
         let path = b"/hello/world/what/now/brown/cow";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 7);
 
-        let expected: Vec<PathComponent<'path>> = vec![
+        let expected: Vec<PathComponent> = vec![
             Ok(Component::RootDir),
             Ok(Component::Normal(OsStr::new(r"hello"))),
             Ok(Component::Normal(OsStr::new(r"world"))),
@@ -316,24 +870,39 @@ mod iter {
     }
 
     #[test]
-    fn empty_path<'path>() {
+    fn empty_path() {
         let path = b"";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
-        let expected: Vec<PathComponent<'path>> = vec![Ok(Component::CurDir)];
+        let expected: Vec<PathComponent> = vec![Ok(Component::CurDir)];
+
+        assert_eq!(comp, expected);
+    }
+
+    #[test]
+    fn dot_prefixed_names() {
+        let path = b"/.hello/..world";
+        let iter = Iter::new(SystemStr::from_bytes(path));
+
+        let comp: Vec<PathComponent> = iter.collect();
+        let expected: Vec<PathComponent> = vec![
+            Ok(Component::RootDir),
+            Ok(Component::Normal(OsStr::new(".hello"))),
+            Ok(Component::Normal(OsStr::new("..world"))),
+        ];
 
         assert_eq!(comp, expected);
     }
 
     #[test]
-    fn multibyte_chars<'path>() {
+    fn multibyte_chars() {
         let s = "/multibyte/Löwe 老虎 Léopard";
         let path = s.as_bytes();
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
-        let expected: Vec<PathComponent<'path>> = vec![
+        let expected: Vec<PathComponent> = vec![
             Ok(Component::RootDir),
             Ok(Component::Normal(OsStr::new("multibyte"))),
             Ok(Component::Normal(OsStr::new("Löwe 老虎 Léopard"))),
@@ -341,6 +910,243 @@ mod iter {
 
         assert_eq!(comp, expected);
     }
+
+    fn reversed(path: &[u8]) -> Vec<PathComponent<'_>> {
+        let mut comp: Vec<PathComponent> =
+            Iter::new(SystemStr::from_bytes(path)).rev().collect();
+        comp.reverse();
+        comp
+    }
+
+    #[test]
+    fn next_back_matches_next() {
+        let paths: Vec<&[u8]> = vec![
+            b"",
+            b"/",
+            b"//",
+            b".",
+            b"./",
+            b"hello",
+            b"hello/",
+            b"/hello",
+            b"/hello/world/",
+            b"hello//world",
+            b"hello/world/../what/./now",
+            b"./hello/world",
+            b"//hello//",
+        ];
+
+        for path in paths {
+            let forward: Vec<PathComponent> =
+                Iter::new(SystemStr::from_bytes(path)).collect();
+            assert_eq!(reversed(path), forward);
+        }
+    }
+
+    #[test]
+    fn next_back_mixed_with_next() {
+        let path = b"/hello/world/what";
+        let mut iter = Iter::new(SystemStr::from_bytes(path));
+
+        assert_eq!(
+            iter.next_back(),
+            Some(Ok(Component::Normal(OsStr::new("what"))))
+        );
+        assert_eq!(iter.next(), Some(Ok(Component::RootDir)));
+        assert_eq!(
+            iter.next(),
+            Some(Ok(Component::Normal(OsStr::new("hello"))))
+        );
+        assert_eq!(
+            iter.next_back(),
+            Some(Ok(Component::Normal(OsStr::new("world"))))
+        );
+        assert_eq!(iter.next_back(), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn next_back_invalid_char() {
+        let path = b"/hello\x00/world";
+        let forward: Vec<PathComponent> =
+            Iter::new(SystemStr::from_bytes(path)).collect();
+
+        let mut iter = Iter::new(SystemStr::from_bytes(path));
+        assert_eq!(
+            iter.next_back(),
+            Some(Ok(Component::Normal(OsStr::new("world"))))
+        );
+
+        // The error is the same as the one found from the front, and it ends
+        // the iteration
+        let err = iter.next_back();
+        assert_eq!(err.as_ref(), forward.get(1));
+        assert!(err.unwrap().is_err());
+        assert_eq!(iter.next_back(), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn spans() {
+        let path = b"/hello//world/";
+        let spans: Vec<_> = Iter::new(SystemStr::from_bytes(path))
+            .spans()
+            .map(|s| s.unwrap())
+            .collect();
+
+        let expected = vec![
+            (0..1, Component::RootDir),
+            (1..6, Component::Normal(OsStr::new("hello"))),
+            (7..7, Component::CurDir),
+            (8..13, Component::Normal(OsStr::new("world"))),
+        ];
+        assert_eq!(spans, expected);
+        assert_eq!(&path[8..13], b"world");
+
+        let mut back: Vec<_> = Iter::new(SystemStr::from_bytes(path))
+            .spans()
+            .rev()
+            .map(|s| s.unwrap())
+            .collect();
+        back.reverse();
+        assert_eq!(back, expected);
+    }
+
+    #[test]
+    fn spans_relative_path() {
+        let path = b"./hello";
+        let spans: Vec<_> = Iter::new(SystemStr::from_bytes(path))
+            .spans()
+            .map(|s| s.unwrap())
+            .collect();
+
+        let expected = vec![
+            (0..1, Component::CurDir),
+            (2..7, Component::Normal(OsStr::new("hello"))),
+        ];
+        assert_eq!(spans, expected);
+    }
+
+    #[test]
+    fn spans_invalid_char() {
+        let path = b"/hello/wor\x00ld";
+        let spans: Vec<_> =
+            Iter::new(SystemStr::from_bytes(path)).spans().collect();
+
+        assert_eq!(spans.len(), 3);
+        assert_eq!(
+            spans[1],
+            Ok((1..6, Component::Normal(OsStr::new("hello"))))
+        );
+        match &spans[2] {
+            Err(e) => assert_eq!(
+                e.kind(),
+                ParseErrorKind::Unix(UnixErrorKind::InvalidCharacter)
+            ),
+            Ok(_) => panic!("expected an error"),
+        }
+    }
+
+    fn rebuild(path: &str) -> OsString {
+        let mut ret = OsString::new();
+        for token in UnixPath::new(path).tokens() {
+            ret.push(token.as_os_str());
+        }
+        ret
+    }
+
+    #[test]
+    fn tokens_rebuild_path() {
+        let paths = vec![
+            "",
+            "/",
+            "//",
+            "///a",
+            "a",
+            "a/",
+            "a//b",
+            "/a/./b/../c//",
+            "./a",
+            "a\\b",
+        ];
+
+        for path in paths {
+            assert_eq!(rebuild(path), OsString::from(path));
+        }
+    }
+
+    #[test]
+    fn tokens() {
+        let tokens: Vec<Token> =
+            UnixPath::new("/hello//world/").tokens().collect();
+        let expected = vec![
+            Token::RootDir,
+            Token::Normal(OsStr::new("hello")),
+            Token::Separator,
+            Token::Empty,
+            Token::Separator,
+            Token::Normal(OsStr::new("world")),
+            Token::Separator,
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn tokens_skip_validation() {
+        let path = SystemStr::from_bytes(b"a/b\x00c");
+        let tokens: Vec<Token> = Tokens::new(path).collect();
+        let expected = vec![
+            Token::Normal(OsStr::new("a")),
+            Token::Separator,
+            Token::Normal(OsStr::new("b\x00c")),
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn recovering() {
+        let path = b"/hel\x00lo/world/wh\x00at/now";
+        let comp: Vec<PathComponent> = Iter::new(SystemStr::from_bytes(path))
+            .recovering()
+            .collect();
+
+        assert_eq!(comp.len(), 5);
+        assert_eq!(comp[0], Ok(Component::RootDir));
+        assert_eq!(comp[2], Ok(Component::Normal(OsStr::new("world"))));
+        assert_eq!(comp[4], Ok(Component::Normal(OsStr::new("now"))));
+
+        for i in &[1, 3] {
+            match &comp[*i] {
+                Err(e) => assert_eq!(
+                    e.kind(),
+                    ParseErrorKind::Unix(UnixErrorKind::InvalidCharacter)
+                ),
+                Ok(_) => panic!("expected an error"),
+            }
+        }
+
+        // Every component is still returned from the back
+        let mut back: Vec<PathComponent> =
+            Iter::new(SystemStr::from_bytes(path))
+                .recovering()
+                .rev()
+                .collect();
+        back.reverse();
+        assert_eq!(back, comp);
+    }
+
+    #[test]
+    fn recovering_last_component() {
+        let path = b"hello/wor\x00ld";
+        let comp: Vec<PathComponent> = Iter::new(SystemStr::from_bytes(path))
+            .recovering()
+            .collect();
+
+        assert_eq!(comp.len(), 2);
+        assert!(comp[1].is_err());
+    }
 }
 
 // ===========================================================================
@@ -23,15 +23,606 @@ use std::path::Prefix;
 // Local imports
 use crate::common::error::*;
 use crate::path::{PathIterator, SystemStr};
-use crate::windows::{Component, Iter, PathComponent, PrefixComponent};
+use crate::windows::{
+    Component, Iter, PathComponent, PrefixComponent, PrefixKind, Token,
+    UncHost, UncShare, WindowsPath,
+};
 
 // ===========================================================================
 // Tests
 // ===========================================================================
 
+mod windowspath {
+    use crate::common::error::*;
+    use crate::path::{Path, SystemSeq};
+    use crate::unix::UnixPathBuf;
+    use crate::windows::{
+        ConvertPolicy, OnInvalid, RootMap, WindowsPath, WindowsPathBuf,
+    };
+    use std::ffi::{OsStr, OsString};
+
+    fn parent(path: &str) -> Option<&OsStr> {
+        WindowsPath::new(path).parent().map(|p| p.as_os_str())
+    }
+
+    #[test]
+    fn parent_disk() {
+        assert_eq!(parent(r"C:\hello\world"), Some(OsStr::new(r"C:\hello")));
+        assert_eq!(parent(r"C:\hello"), Some(OsStr::new(r"C:\")));
+        assert_eq!(parent(r"C:\"), None);
+        assert_eq!(parent(r"C:foo"), Some(OsStr::new(r"C:")));
+        assert_eq!(parent(r"C:"), None);
+    }
+
+    #[test]
+    fn parent_unc() {
+        assert_eq!(
+            parent(r"\\server\share\hello"),
+            Some(OsStr::new(r"\\server\share\"))
+        );
+        assert_eq!(parent(r"\\server\share\"), None);
+        assert_eq!(parent(r"\\server\share"), None);
+    }
+
+    #[test]
+    fn parent_verbatim() {
+        assert_eq!(parent(r"\\?\C:\hello"), Some(OsStr::new(r"\\?\C:\")));
+        assert_eq!(parent(r"\\?\C:\"), None);
+        assert_eq!(
+            parent(r"\\?\UNC\server\share\hello"),
+            Some(OsStr::new(r"\\?\UNC\server\share\"))
+        );
+        assert_eq!(parent(r"\\?\UNC\server\share\"), None);
+    }
+
+    #[test]
+    fn parent_relative() {
+        assert_eq!(parent(r"hello\world"), Some(OsStr::new("hello")));
+        assert_eq!(parent(r"\hello"), Some(OsStr::new(r"\")));
+        assert_eq!(parent(r"\"), None);
+        assert_eq!(parent("hello"), Some(OsStr::new("")));
+        assert_eq!(parent(""), None);
+    }
+
+    #[test]
+    fn ancestors() {
+        let path = WindowsPath::new(r"\\server\share\hello\world");
+        let ancestors: Vec<&OsStr> =
+            path.ancestors().map(|p| p.as_os_str()).collect();
+        let expected: Vec<&OsStr> = [
+            r"\\server\share\hello\world",
+            r"\\server\share\hello",
+            r"\\server\share\",
+        ]
+        .iter()
+        .map(OsStr::new)
+        .collect();
+        assert_eq!(ancestors, expected);
+
+        let path = WindowsPathBuf::from(r"C:hello\world");
+        let ancestors: Vec<&OsStr> =
+            path.ancestors().map(|p| p.as_os_str()).collect();
+        let expected: Vec<&OsStr> = [r"C:hello\world", "C:hello", "C:"]
+            .iter()
+            .map(OsStr::new)
+            .collect();
+        assert_eq!(ancestors, expected);
+    }
+
+    fn normalize(path: &str) -> WindowsPathBuf {
+        WindowsPath::new(path).normalize().unwrap()
+    }
+
+    #[test]
+    fn normalize_prefix() {
+        for (path, expected) in &[
+            (r"C:\hello\\.\world\", r"C:\hello\world"),
+            (r"C:/hello/../world", r"C:\world"),
+            (r"C:\..\hello", r"C:\hello"),
+            (r"C:\..", r"C:\"),
+            (r"C:hello\..", r"C:"),
+            (r"C:hello\..\..", r"C:.."),
+            (r"C:..\hello", r"C:..\hello"),
+            (r"\\server\share\hello\..\..", r"\\server\share\"),
+        ] {
+            assert_eq!(normalize(path).as_os_str(), OsStr::new(expected));
+        }
+    }
+
+    #[test]
+    fn normalize_noprefix() {
+        for (path, expected) in &[
+            (r"\hello\..\..\world", r"\world"),
+            (r"hello/./world", r"hello\world"),
+            (r"hello\..", "."),
+            ("", "."),
+            (r"..\hello\..\..", r"..\.."),
+        ] {
+            assert_eq!(normalize(path).as_os_str(), OsStr::new(expected));
+        }
+    }
+
+    #[test]
+    fn normalize_verbatim_unchanged() {
+        for path in &[
+            r"\\?\C:\hello\..\world\.",
+            r"\\?\UNC\server\share\hello\..",
+            r"\\?\hello\\world",
+        ] {
+            assert_eq!(normalize(path).as_os_str(), OsStr::new(path));
+        }
+    }
+
+    #[test]
+    fn normalize_invalid() {
+        let path = WindowsPathBuf::from(r"C:\hello\CON\..");
+        let err = path.normalize().unwrap_err();
+        assert_eq!(err.kind(), WindowsErrorKind::RestrictedName.into());
+    }
+
+    #[test]
+    fn clean() {
+        let path = WindowsPathBuf::from(r"C:\hello\\world\..\now");
+        assert_eq!(
+            path.clean().unwrap(),
+            WindowsPathBuf::from(r"C:\hello\now")
+        );
+    }
+
+    fn relative_to(
+        path: &str,
+        base: &str,
+    ) -> Result<WindowsPathBuf, RelativePathError> {
+        WindowsPath::new(path).relative_to(base)
+    }
+
+    #[test]
+    fn relative_to_prefix() {
+        for (path, base, expected) in &[
+            (r"C:\a\b\x\y", r"C:\a\b", r"x\y"),
+            (r"C:\a\x", r"c:/a/b/c", r"..\..\x"),
+            (r"C:a\x", r"C:a\b", r"..\x"),
+            (r"C:..\x", r"C:..\b", r"..\x"),
+            (r"\\server\share\a\x", r"\\SERVER\Share\a\b", r"..\x"),
+            (r"C:\a", r"C:\a", "."),
+        ] {
+            let relative = relative_to(path, base).unwrap();
+            assert_eq!(relative.as_os_str(), OsStr::new(expected));
+        }
+    }
+
+    #[test]
+    fn relative_to_ignores_case() {
+        for (path, base, expected) in &[
+            (r"C:\Users\Foo\x", r"C:\users\foo", "x"),
+            (r"C:\A\x", r"c:\a\B", r"..\x"),
+            (r"dir\File", r"DIR\file", "."),
+        ] {
+            let relative = relative_to(path, base).unwrap();
+            assert_eq!(relative.as_os_str(), OsStr::new(expected));
+        }
+    }
+
+    #[test]
+    fn relative_to_noprefix() {
+        for (path, base, expected) in &[
+            (r"\a\x", r"\a\b", r"..\x"),
+            (r"a\.\x", r"a\b\..\c", r"..\x"),
+            (r"..\x", r"..\b", r"..\x"),
+        ] {
+            let relative = relative_to(path, base).unwrap();
+            assert_eq!(relative.as_os_str(), OsStr::new(expected));
+        }
+    }
+
+    #[test]
+    fn relative_to_error() {
+        for (path, base) in &[
+            (r"C:\a", r"D:\a"),
+            (r"\\server\share\a", r"\\server\other\a"),
+            (r"\\server\share\a", r"\\other\share\a"),
+            (r"C:\a", r"\a"),
+            (r"C:\a", r"\\?\C:\a"),
+        ] {
+            let err = relative_to(path, base).unwrap_err();
+            assert_eq!(err, RelativePathError::PrefixMismatch);
+        }
+
+        let err = relative_to(r"C:\a", r"C:a").unwrap_err();
+        assert_eq!(err, RelativePathError::RootMismatch);
+
+        let err = relative_to(r"\a", r"a").unwrap_err();
+        assert_eq!(err, RelativePathError::RootMismatch);
+
+        let err = relative_to(r"a", r"..\b").unwrap_err();
+        assert_eq!(err, RelativePathError::UnknownBase);
+
+        let err = relative_to(r"C:x", r"C:..\b").unwrap_err();
+        assert_eq!(err, RelativePathError::UnknownBase);
+
+        let err = relative_to(r"C:\a", r"C:\CON").unwrap_err();
+        match err {
+            RelativePathError::Parse(e) => {
+                assert_eq!(e.kind(), WindowsErrorKind::RestrictedName.into())
+            }
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn as_posix() {
+        let paths = vec![
+            (r"C:\dir\file.txt", "C:/dir/file.txt"),
+            (r"a/b\c", "a/b/c"),
+            (r"\\server\share\x", "//server/share/x"),
+            (r"C:relative\x", "C:relative/x"),
+            (r"\\?\C:\dir\a/b", r"\\?\C:\dir\a/b"),
+            ("", ""),
+        ];
+        for (path, expected) in paths {
+            let shown = WindowsPath::new(path).as_posix().to_string();
+            assert_eq!(shown, expected);
+        }
+    }
+
+    #[test]
+    fn as_windows() {
+        let paths = vec![
+            (r"C:/dir\file.txt", r"C:\dir\file.txt"),
+            (r"a/b\c", r"a\b\c"),
+            ("//server/share/x", r"\\server\share\x"),
+            (r"\\?\C:\dir\a/b", r"\\?\C:\dir\a/b"),
+            ("老虎/Löwe//", r"老虎\Löwe\\"),
+            ("/", r"\"),
+        ];
+        for (path, expected) in paths {
+            let shown = WindowsPath::new(path).as_windows().to_string();
+            assert_eq!(shown, expected);
+        }
+
+        let pathbuf = WindowsPathBuf::from(r"out\bin/app.exe");
+        assert_eq!(pathbuf.as_posix().to_string(), "out/bin/app.exe");
+        assert_eq!(pathbuf.as_windows().to_string(), r"out\bin\app.exe");
+    }
+
+    #[test]
+    fn to_unix_roots() {
+        let drive = ConvertPolicy::new().with_root(RootMap::Drive(b'C'));
+        let paths = vec![
+            (r"C:\src\main.rs", "/src/main.rs"),
+            (r"c:/src/./x/../y", "/src/./x/../y"),
+            (r"\\?\C:\src", "/src"),
+            (r"C:\", "/"),
+            (r"src\main.rs", "src/main.rs"),
+            ("", ""),
+        ];
+        for (windows, unix) in paths {
+            let converted = WindowsPath::new(windows).to_unix(&drive);
+            assert_eq!(converted, Ok(UnixPathBuf::from(unix)), "{}", windows);
+        }
+
+        let unc = ConvertPolicy::new().with_root(RootMap::Unc {
+            server: "build".into(),
+            share: "out".into(),
+        });
+        let converted = WindowsPath::new(r"\\BUILD\Out\bin").to_unix(&unc);
+        assert_eq!(converted, Ok(UnixPathBuf::from("/bin")));
+
+        let converted =
+            WindowsPath::new(r"\a\b").to_unix(&ConvertPolicy::new());
+        assert_eq!(converted, Ok(UnixPathBuf::from("/a/b")));
+    }
+
+    #[test]
+    fn to_unix_unmapped_root() {
+        let drive = ConvertPolicy::new().with_root(RootMap::Drive(b'C'));
+        let paths = [r"D:\src", r"C:src", "C:", r"\src", r"\\server\share\x"];
+        for path in paths.iter() {
+            let err = WindowsPath::new(path).to_unix(&drive).unwrap_err();
+            assert_eq!(err, ConvertError::UnmappedRoot, "{}", path);
+        }
+
+        let err = WindowsPath::new(r"C:\src")
+            .to_unix(&ConvertPolicy::new())
+            .unwrap_err();
+        assert_eq!(err, ConvertError::UnmappedRoot);
+    }
+
+    #[test]
+    fn to_unix_invalid_names() {
+        let drive = ConvertPolicy::new().with_root(RootMap::Drive(b'C'));
+        let path = WindowsPath::new(r"\\?\C:\a/b\..");
+        let err = path.to_unix(&drive).unwrap_err();
+        match err {
+            ConvertError::InvalidName(e) => {
+                assert_eq!(e.kind(), UnixErrorKind::InvalidCharacter.into());
+                assert_eq!(e.component(), OsStr::new("a/b"));
+            }
+            _ => panic!("expected an invalid name"),
+        }
+
+        let escape = drive.on_invalid_char(OnInvalid::Escape);
+        let converted = path.to_unix(&escape);
+        assert_eq!(converted, Ok(UnixPathBuf::from("/a%2Fb/%2E%2E")));
+
+        let converted = WindowsPath::new(r"C:\a%3Ab%zz").to_unix(&escape);
+        assert_eq!(converted, Ok(UnixPathBuf::from("/a:b%zz")));
+
+        // A Windows name that holds %XX is decoded even if it was not
+        // escaped, so it does not convert back unchanged
+        let converted = WindowsPath::new(r"C:\100%41").to_unix(&escape);
+        assert_eq!(converted, Ok(UnixPathBuf::from("/100A")));
+        let back = converted.unwrap().to_windows(&escape);
+        assert_eq!(back, Ok(WindowsPathBuf::from(r"C:\100A")));
+
+        let pathbuf = WindowsPathBuf::from(r"x\y");
+        assert_eq!(pathbuf.to_unix(&escape), Ok(UnixPathBuf::from("x/y")));
+
+        let private_use = escape.on_invalid_char(OnInvalid::PrivateUse);
+        let converted = path.to_unix(&private_use);
+        let expected = "/a\u{f02f}b/\u{f02e}\u{f02e}";
+        assert_eq!(converted, Ok(UnixPathBuf::from(expected)));
+
+        let converted =
+            WindowsPath::new("C:\\a\u{f03a}b%3A").to_unix(&private_use);
+        assert_eq!(converted, Ok(UnixPathBuf::from("/a:b%3A")));
+    }
+
+    #[test]
+    fn pathbuf_parent() {
+        let path = WindowsPathBuf::from(r"C:\hello\world");
+        let relative = path.relative_to(r"C:\hello\now").unwrap();
+        assert_eq!(relative, WindowsPathBuf::from(r"..\world"));
+        let relative = path.relative_to(r"C:\hello\now").unwrap();
+        assert_eq!(relative, WindowsPathBuf::from(r"..\world"));
+        assert_eq!(path.parent(), Some(WindowsPath::new(r"C:\hello")));
+        assert_eq!(path.as_path(), WindowsPath::new(r"C:\hello\world"));
+    }
+
+    #[test]
+    fn validate_all_clean() {
+        let path = WindowsPath::new(r"C:\hello\.\world\..\now\");
+        assert!(path.validate_all().is_empty());
+        assert!(WindowsPath::new("").validate_all().is_empty());
+    }
+
+    #[test]
+    fn validate_all() {
+        let path = WindowsPath::new(r"C:\a<b\hello\nul.txt\what?\con");
+        let errors = path.validate_all();
+
+        let kinds: Vec<ParseErrorKind> =
+            errors.iter().map(|e| e.kind()).collect();
+        let expected = vec![
+            ParseErrorKind::Windows(WindowsErrorKind::InvalidCharacter),
+            ParseErrorKind::Windows(WindowsErrorKind::RestrictedName),
+            ParseErrorKind::Windows(WindowsErrorKind::InvalidCharacter),
+            ParseErrorKind::Windows(WindowsErrorKind::RestrictedName),
+        ];
+        assert_eq!(kinds, expected);
+
+        let pathbuf = WindowsPathBuf::from(path.as_os_str());
+        assert_eq!(pathbuf.validate_all(), errors);
+    }
+
+    #[test]
+    fn is_reserved() {
+        let reserved = vec![
+            r"C:\dir\nul",
+            r"dir\CONIN$",
+            r"conout$.log",
+            r"COM0",
+            r"lpt0.txt",
+            r"dir\COM¹\file",
+            r"LPT³.txt",
+            r"NUL .txt",
+            r"aux.tar.gz",
+            r"con:",
+            r"\\server\share\prn",
+        ];
+        for path in reserved {
+            assert!(WindowsPath::new(path).is_reserved(), "{}", path);
+        }
+
+        let not_reserved = vec![
+            r"C:\dir\null",
+            r"console",
+            r"COM10",
+            r"COM⁴",
+            r"file.nul",
+            r"\\.\COM1",
+            r"\\?\C:\nul",
+        ];
+        for path in not_reserved {
+            assert!(!WindowsPath::new(path).is_reserved(), "{}", path);
+        }
+
+        let pathbuf = WindowsPathBuf::from(r"dir\CONOUT$");
+        assert!(pathbuf.is_reserved());
+    }
+
+    fn simplify(path: &str) -> OsString {
+        let simple = WindowsPath::new(path).simplify_verbatim();
+        simple.as_os_str().to_os_string()
+    }
+
+    #[test]
+    fn simplify_verbatim() {
+        assert_eq!(simplify(r"\\?\C:\dir\file"), r"C:\dir\file");
+        assert_eq!(simplify(r"\\?\C:\"), r"C:\");
+        assert_eq!(simplify(r"\\?\UNC\srv\share\x"), r"\\srv\share\x");
+        assert_eq!(simplify(r"\\?\UNC\srv\share"), r"\\srv\share");
+
+        // Paths without a verbatim prefix are left alone
+        assert_eq!(simplify(r"C:\dir\file"), r"C:\dir\file");
+        assert_eq!(simplify(r"dir\file"), r"dir\file");
+
+        let pathbuf = WindowsPathBuf::from(r"\\?\D:\x");
+        assert_eq!(pathbuf.simplify_verbatim(), WindowsPathBuf::from(r"D:\x"));
+    }
+
+    #[test]
+    fn simplify_verbatim_unsafe() {
+        // Each of these means something else without the prefix
+        let paths = vec![
+            r"\\?\C:\dir\nul",
+            r"\\?\C:\dir\file.",
+            r"\\?\C:\dir\file ",
+            r"\\?\C:\a/b",
+            r"\\?\C:\dir\..\x",
+            r"\\?\C:\dir\.\x",
+            r"\\?\GLOBALROOT\Device\HarddiskVolume1\x",
+            r"\\?\Volume{26a21bda-a627-11d7-9931-806e6f6e6963}\x",
+            r"\??\C:\x",
+        ];
+        for path in paths {
+            assert_eq!(simplify(path), path);
+        }
+
+        let long = format!(r"\\?\C:\{}", "a".repeat(300));
+        assert_eq!(simplify(&long), OsString::from(&long));
+    }
+
+    #[test]
+    fn to_verbatim() {
+        let verbatim = |path: &str, limit| {
+            let path = WindowsPath::new(path);
+            let pathbuf = path.to_verbatim_with_limit(limit).unwrap();
+            pathbuf.as_os_str().to_os_string()
+        };
+        assert_eq!(verbatim(r"C:\dir\..\x\.\y", 0), r"\\?\C:\x\y");
+        assert_eq!(verbatim(r"C:/x/y", 0), r"\\?\C:\x\y");
+        assert_eq!(verbatim(r"C:\", 0), r"\\?\C:\");
+        assert_eq!(verbatim(r"\\srv\share\x\..\y", 0), r"\\?\UNC\srv\share\y");
+        assert_eq!(verbatim(r"//srv/share", 0), r"\\?\UNC\srv\share");
+
+        // Short paths are only normalized
+        assert_eq!(verbatim(r"C:\dir\..\x", 5), r"C:\x");
+        assert_eq!(verbatim(r"C:\dir\..\x", 4), r"\\?\C:\x");
+
+        // There is no verbatim form for paths that are not absolute
+        assert_eq!(verbatim(r"dir\..\x", 0), r"x");
+        assert_eq!(verbatim(r"C:dir\x", 0), r"C:dir\x");
+        assert_eq!(verbatim(r"\dir\x", 0), r"\dir\x");
+        assert_eq!(verbatim(r"\\?\C:\a\..", 0), r"\\?\C:\a\..");
+    }
+
+    #[test]
+    fn to_verbatim_max_path() {
+        let dir = "d".repeat(100);
+        let short = format!(r"C:\{}\{}", dir, dir);
+        let pathbuf = WindowsPath::new(&short).to_verbatim().unwrap();
+        assert_eq!(pathbuf.as_os_str(), OsStr::new(&short));
+
+        let long = format!(r"C:\{}\{}\{}", dir, dir, dir);
+        let pathbuf = WindowsPathBuf::from(&long).to_verbatim().unwrap();
+        let expected = format!(r"\\?\{}", long);
+        assert_eq!(pathbuf.as_os_str(), OsStr::new(&expected));
+
+        // The result still means the same path
+        assert_eq!(pathbuf.simplify_verbatim(), pathbuf);
+    }
+
+    #[test]
+    fn to_verbatim_invalid() {
+        let path = WindowsPath::new(r"C:\dir\nul\x");
+        assert!(path.to_verbatim().is_err());
+    }
+
+    #[test]
+    fn has_short_names() {
+        assert!(WindowsPath::new(r"C:\PROGRA~1\app").has_short_names());
+        assert!(WindowsPath::new(r"docs\mydocu~2.txt").has_short_names());
+        assert!(!WindowsPath::new(r"C:\Program Files\app").has_short_names());
+        assert!(
+            !WindowsPath::new(r"C:\~1\a~b\x~0\toolongname~1").has_short_names()
+        );
+
+        let pathbuf = WindowsPathBuf::from(r"C:\PROGRA~1");
+        assert!(pathbuf.has_short_names());
+    }
+
+    #[test]
+    fn may_alias() {
+        let path = WindowsPath::new(r"C:\Program Files\My App\readme.txt");
+        assert!(path.may_alias(r"C:\PROGRA~1\MYAPP~1\README.TXT"));
+        assert!(path.may_alias(r"c:/progra~2/My App/readme.txt"));
+        assert!(WindowsPath::new(r"C:\PROGRA~1").may_alias(r"C:\Program Files"));
+
+        assert!(!path.may_alias(r"C:\PROGRA~1\MYAPP~1"));
+        assert!(!path.may_alias(r"D:\PROGRA~1\MYAPP~1\README.TXT"));
+        assert!(!path.may_alias(r"C:\OTHER~1\MYAPP~1\README.TXT"));
+        assert!(!path.may_alias(r"C:\Program Files\My App\README~1.TXT"));
+
+        let pathbuf = WindowsPathBuf::from(r"\\srv\share\Documents");
+        assert!(pathbuf.may_alias(r"\\SRV\share\DOCUME~1"));
+    }
+
+    #[test]
+    fn to_disk_path() {
+        let disk = |path: &str| {
+            WindowsPath::new(path)
+                .to_disk_path()
+                .map(|p| p.as_os_str().to_os_string())
+        };
+        assert_eq!(disk(r"\\localhost\C$\x\y"), Some(r"C:\x\y".into()));
+        assert_eq!(disk(r"\\LOCALHOST\d$"), Some(r"D:\".into()));
+        assert_eq!(disk(r"\\127.0.0.1\c$\x"), Some(r"C:\x".into()));
+        assert_eq!(disk(r"\\--1.ipv6-literal.net\C$\x"), Some(r"C:\x".into()));
+        assert_eq!(disk(r"\\?\UNC\localhost\C$\x"), Some(r"\\?\C:\x".into()));
+
+        // Only the drive shares of this machine are the same as a drive
+        assert_eq!(disk(r"\\server\C$\x"), None);
+        assert_eq!(disk(r"\\localhost\share\x"), None);
+        assert_eq!(disk(r"\\localhost\ADMIN$\x"), None);
+        assert_eq!(disk(r"C:\x"), None);
+
+        let pathbuf = WindowsPathBuf::from(r"\\localhost\C$\x");
+        assert_eq!(pathbuf.to_disk_path(), Some(WindowsPathBuf::from(r"C:\x")));
+    }
+
+    #[test]
+    fn file_stream() {
+        let path = WindowsPath::new(r"C:\dl\file.txt:Zone.Identifier:$DATA");
+        let stream = path.file_stream().unwrap();
+        assert_eq!(stream.base(), OsStr::new("file.txt"));
+        assert_eq!(stream.stream(), Some(OsStr::new("Zone.Identifier")));
+        assert_eq!(stream.stream_type(), Some(OsStr::new("$DATA")));
+
+        // Streams are opt-in, so the default parse still rejects them
+        assert_eq!(path.file_name(), None);
+
+        let stream = WindowsPath::new(r"C:\dl\file.txt").file_stream().unwrap();
+        assert_eq!(stream.base(), OsStr::new("file.txt"));
+        assert_eq!(stream.stream(), None);
+
+        let pathbuf = WindowsPathBuf::from(r"dl\file.txt::$DATA");
+        let stream = pathbuf.file_stream().unwrap();
+        assert_eq!(stream.stream(), Some(OsStr::new("")));
+        assert_eq!(stream.stream_type(), Some(OsStr::new("$DATA")));
+    }
+
+    #[test]
+    fn file_stream_invalid() {
+        let paths = vec![
+            r"C:\dl:s\file.txt",
+            r"C:\dl\file.txt:",
+            r"C:\dl\file.txt:s:DATA",
+            r"C:\dl\file.txt:s:$DATA:x",
+            r"C:\dl\nul:s",
+            r"C:\dl\:s",
+        ];
+        for path in paths {
+            assert_eq!(WindowsPath::new(path).file_stream(), None, "{}", path);
+        }
+    }
+}
+
 mod windowspathbuf {
+    use crate::common::error::*;
     use crate::path::{SystemSeq, SystemString};
-    use crate::windows::WindowsPathBuf;
+    use crate::windows::{WindowsPath, WindowsPathBuf};
+    use std::ffi::OsStr;
 
     #[test]
     fn deref_to_systemstring() {
@@ -39,6 +630,244 @@ mod windowspathbuf {
         let inner: &SystemString = &path;
         assert_eq!(inner.as_os_str().len(), 0);
     }
+
+    #[test]
+    fn push_relative() {
+        let mut path = WindowsPathBuf::from(r"C:\hello");
+        path.push("world");
+        assert_eq!(path.as_os_str(), OsStr::new(r"C:\hello\world"));
+
+        let mut path = WindowsPathBuf::from("C:/hello/");
+        path.push(r"world\now");
+        assert_eq!(path.as_os_str(), OsStr::new(r"C:/hello/world\now"));
+    }
+
+    #[test]
+    fn push_onto_drive_only() {
+        let mut path = WindowsPathBuf::from("C:");
+        path.push("hello");
+        assert_eq!(path.as_os_str(), OsStr::new("C:hello"));
+    }
+
+    #[test]
+    fn push_onto_unc_share() {
+        let mut path = WindowsPathBuf::from(r"\\server\share");
+        path.push("hello");
+        assert_eq!(path.as_os_str(), OsStr::new(r"\\server\share\hello"));
+    }
+
+    #[test]
+    fn push_absolute_replaces() {
+        let mut path = WindowsPathBuf::from(r"C:\hello");
+        path.push(r"D:\world");
+        assert_eq!(path.as_os_str(), OsStr::new(r"D:\world"));
+
+        path.push(r"\\server\share\what");
+        assert_eq!(path.as_os_str(), OsStr::new(r"\\server\share\what"));
+    }
+
+    #[test]
+    fn push_drive_relative_switches_drive() {
+        let mut path = WindowsPathBuf::from(r"C:\x");
+        path.push("D:foo");
+        assert_eq!(path.as_os_str(), OsStr::new("D:foo"));
+    }
+
+    #[test]
+    fn push_root_keeps_prefix() {
+        let mut path = WindowsPathBuf::from(r"C:\hello\world");
+        path.push(r"\what");
+        assert_eq!(path.as_os_str(), OsStr::new(r"C:\what"));
+
+        let mut path = WindowsPathBuf::from(r"\\server\share\hello");
+        path.push(r"\what");
+        assert_eq!(path.as_os_str(), OsStr::new(r"\\server\share\what"));
+
+        let mut path = WindowsPathBuf::from(r"\\?\C:\hello");
+        path.push(r"\what");
+        assert_eq!(path.as_os_str(), OsStr::new(r"\\?\C:\what"));
+
+        let mut path = WindowsPathBuf::from(r"hello\world");
+        path.push(r"\what");
+        assert_eq!(path.as_os_str(), OsStr::new(r"\what"));
+    }
+
+    #[test]
+    fn pop() {
+        let mut path = WindowsPathBuf::from(r"C:\hello\\world\");
+        assert!(path.pop());
+        assert_eq!(path.as_os_str(), OsStr::new(r"C:\hello"));
+
+        assert!(path.pop());
+        assert_eq!(path.as_os_str(), OsStr::new(r"C:\"));
+
+        assert!(!path.pop());
+        assert_eq!(path.as_os_str(), OsStr::new(r"C:\"));
+    }
+
+    #[test]
+    fn pop_drive_relative() {
+        let mut path = WindowsPathBuf::from("C:foo");
+        assert!(path.pop());
+        assert_eq!(path.as_os_str(), OsStr::new("C:"));
+        assert!(!path.pop());
+    }
+
+    #[test]
+    fn pop_prefix_root() {
+        let mut path = WindowsPathBuf::from(r"\\server\share\hello");
+        assert!(path.pop());
+        assert_eq!(path.as_os_str(), OsStr::new(r"\\server\share\"));
+        assert!(!path.pop());
+
+        let mut path = WindowsPathBuf::from(r"\\?\C:\");
+        assert!(!path.pop());
+    }
+
+    #[test]
+    fn join() {
+        let path = WindowsPath::new(r"C:\hello");
+        let joined = path.join("world");
+        assert_eq!(joined.as_os_str(), OsStr::new(r"C:\hello\world"));
+
+        let joined = joined.join(r"\\server\share");
+        assert_eq!(joined.as_os_str(), OsStr::new(r"\\server\share"));
+    }
+
+    #[test]
+    fn with_capacity() {
+        let path = WindowsPathBuf::with_capacity(10);
+        assert_eq!(path, WindowsPathBuf::new());
+    }
+
+    #[test]
+    fn set_file_name() {
+        let mut path = WindowsPathBuf::from(r"C:\hello\world.txt");
+        path.set_file_name("what.rs").unwrap();
+        assert_eq!(path.as_os_str(), OsStr::new(r"C:\hello\what.rs"));
+
+        // Trailing separators and curdir after the file name are dropped
+        let mut path = WindowsPathBuf::from(r"hello\world\.\");
+        path.set_file_name("now").unwrap();
+        assert_eq!(path.as_os_str(), OsStr::new(r"hello\now"));
+
+        let mut path = WindowsPathBuf::from(r"C:world");
+        path.set_file_name("now").unwrap();
+        assert_eq!(path.as_os_str(), OsStr::new(r"C:now"));
+    }
+
+    #[test]
+    fn set_file_name_no_file_name() {
+        let mut path = WindowsPathBuf::from(r"C:\");
+        path.set_file_name("hello").unwrap();
+        assert_eq!(path.as_os_str(), OsStr::new(r"C:\hello"));
+
+        let mut path = WindowsPathBuf::from(r"C:");
+        path.set_file_name("hello").unwrap();
+        assert_eq!(path.as_os_str(), OsStr::new(r"C:hello"));
+
+        let mut path = WindowsPathBuf::from(r"\\server\share");
+        path.set_file_name("hello").unwrap();
+        assert_eq!(path.as_os_str(), OsStr::new(r"\\server\share\hello"));
+    }
+
+    #[test]
+    fn set_file_name_invalid() {
+        let mut path = WindowsPathBuf::from(r"C:\hello\world");
+        for (name, kind) in &[
+            ("CON", WindowsErrorKind::RestrictedName),
+            ("what?", WindowsErrorKind::InvalidCharacter),
+            ("what.", WindowsErrorKind::InvalidCharacter),
+            (r"what\now", WindowsErrorKind::InvalidCharacter),
+            ("what/now", WindowsErrorKind::InvalidCharacter),
+            ("D:what", WindowsErrorKind::InvalidCharacter),
+            ("", WindowsErrorKind::RestrictedName),
+            (".", WindowsErrorKind::RestrictedName),
+            ("..", WindowsErrorKind::RestrictedName),
+        ] {
+            let err = path.set_file_name(*name).unwrap_err();
+            assert_eq!(err.kind(), ParseErrorKind::Windows(*kind));
+        }
+        assert_eq!(path.as_os_str(), OsStr::new(r"C:\hello\world"));
+    }
+
+    #[test]
+    fn set_extension() {
+        let mut path = WindowsPathBuf::from(r"C:\hello\world.tar.gz");
+        assert!(path.set_extension("xz").unwrap());
+        assert_eq!(path.as_os_str(), OsStr::new(r"C:\hello\world.tar.xz"));
+
+        assert!(path.set_extension("").unwrap());
+        assert_eq!(path.as_os_str(), OsStr::new(r"C:\hello\world.tar"));
+
+        let mut path = WindowsPathBuf::from(r"C:\");
+        assert!(!path.set_extension("txt").unwrap());
+        assert_eq!(path.as_os_str(), OsStr::new(r"C:\"));
+
+        let mut path = WindowsPathBuf::from(r"C:\hello\world");
+        let err = path.set_extension("t*t").unwrap_err();
+        assert_eq!(err.kind(), WindowsErrorKind::InvalidCharacter.into());
+    }
+
+    #[test]
+    fn with_name() {
+        let path = WindowsPathBuf::from(r"C:\hello\world.txt");
+        let renamed = path.with_name("now.rs").unwrap();
+        assert_eq!(renamed.as_os_str(), OsStr::new(r"C:\hello\now.rs"));
+        assert_eq!(path.as_os_str(), OsStr::new(r"C:\hello\world.txt"));
+
+        let err = path.with_name("CON").unwrap_err();
+        assert_eq!(err.kind(), WindowsErrorKind::RestrictedName.into());
+    }
+
+    #[test]
+    fn with_stem() {
+        let path = WindowsPathBuf::from(r"C:\hello\world.tar.gz");
+        let renamed = path.with_stem("now").unwrap();
+        assert_eq!(renamed.as_os_str(), OsStr::new(r"C:\hello\now.gz"));
+
+        let err = path.with_stem("PRN").unwrap_err();
+        assert_eq!(err.kind(), WindowsErrorKind::RestrictedName.into());
+    }
+
+    #[test]
+    fn with_suffix() {
+        let path = WindowsPathBuf::from(r"C:\hello\world.txt");
+        let renamed = path.with_suffix(".rs").unwrap();
+        assert_eq!(renamed.as_os_str(), OsStr::new(r"C:\hello\world.rs"));
+
+        let renamed = path.with_suffix("").unwrap();
+        assert_eq!(renamed.as_os_str(), OsStr::new(r"C:\hello\world"));
+
+        let renamed = path.with_suffix(".tar.gz").unwrap();
+        assert_eq!(renamed.as_os_str(), OsStr::new(r"C:\hello\world.tar.gz"));
+
+        let renamed = path.with_suffix(". x").unwrap();
+        assert_eq!(renamed.as_os_str(), OsStr::new(r"C:\hello\world. x"));
+    }
+
+    #[test]
+    fn with_suffix_invalid() {
+        let path = WindowsPathBuf::from(r"C:\hello\world.txt");
+        for suffix in &["rs", ".", ".r ", "..", ".r?", r".a\b", ".a/b"] {
+            match path.with_suffix(*suffix).unwrap_err() {
+                SuffixError::Parse(e) => assert_eq!(
+                    e.kind(),
+                    WindowsErrorKind::InvalidCharacter.into()
+                ),
+                err => panic!("unexpected error: {:?}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn with_suffix_no_file_name() {
+        for path in &[r"C:\", "", r"C:\hello\.."] {
+            let path = WindowsPathBuf::from(*path);
+            let err = path.with_suffix(".rs").unwrap_err();
+            assert_eq!(err, SuffixError::NoFileName);
+        }
+    }
 }
 
 mod public_export {
@@ -61,6 +890,56 @@ mod public_export {
 
             assert!(err.source().is_none());
         }
+
+        fn restricted_name() -> ParseError {
+            ParseError::new(
+                WindowsErrorKind::RestrictedName.into(),
+                OsString::from("nul"),
+                OsString::from(r"C:\hello\nul\world"),
+                9,
+                12,
+                String::from("component uses a restricted name"),
+            )
+        }
+
+        #[test]
+        fn accessors() {
+            let error = restricted_name();
+
+            assert_eq!(
+                error.kind(),
+                ParseErrorKind::Windows(WindowsErrorKind::RestrictedName)
+            );
+            assert_eq!(error.component(), OsStr::new("nul"));
+            assert_eq!(error.path(), OsStr::new(r"C:\hello\nul\world"));
+            assert_eq!(error.start(), 9);
+            assert_eq!(error.end(), 12);
+            assert_eq!(error.span(), 9..12);
+            assert_eq!(error.msg(), "component uses a restricted name");
+        }
+
+        #[test]
+        fn accessors_from_iter() {
+            let path = WindowsPath::new(r"C:\hello\wor<ld");
+            let error = path.validate_all().remove(0);
+
+            assert_eq!(error.path(), OsStr::new(r"C:\hello\wor<ld"));
+            assert_eq!(error.start(), 9);
+            assert_eq!(error.span(), error.start()..error.end());
+        }
+
+        #[test]
+        fn diagnostic() {
+            let expected = [
+                "error: component uses a restricted name",
+                "  |",
+                r"  | C:\hello\nul\world",
+                "  |          ^^^",
+            ];
+            let rendered = restricted_name().diagnostic().to_string();
+
+            assert_eq!(rendered, expected.join("\n"));
+        }
     }
 
     mod component {
@@ -122,25 +1001,107 @@ mod public_export {
         fn kind() {
             let path = br#"\\?\hello\world"#;
             let prefix = Prefix::Verbatim(OsStr::new("hello"));
-            let prefix_comp = PrefixComponent::new(&path[..], prefix.clone());
+            let prefix_comp = PrefixComponent::new(&path[..], prefix);
 
             assert_eq!(prefix_comp.kind(), prefix);
         }
     }
+
+    mod prefixkind {
+        use super::*;
+
+        fn classify(path: &str) -> Option<PrefixKind<'_>> {
+            match Iter::new(SystemStr::new(path)).next() {
+                Some(Ok(Component::Prefix(prefix))) => Some(prefix.classify()),
+                _ => None,
+            }
+        }
+
+        #[test]
+        fn std_prefixes() {
+            let os = OsStr::new;
+            assert_eq!(classify(r"C:\hello"), Some(PrefixKind::Disk(b'C')));
+            assert_eq!(
+                classify(r"\\server\share\hello"),
+                Some(PrefixKind::UNC(os("server"), os("share")))
+            );
+            assert_eq!(
+                classify(r"\\?\C:\hello"),
+                Some(PrefixKind::VerbatimDisk(b'C'))
+            );
+            assert_eq!(
+                classify(r"\\?\UNC\server\share"),
+                Some(PrefixKind::VerbatimUNC(os("server"), os("share")))
+            );
+            assert_eq!(
+                classify(r"\\?\hello\world"),
+                Some(PrefixKind::Verbatim(os("hello")))
+            );
+            assert_eq!(
+                classify(r"\\.\COM1"),
+                Some(PrefixKind::DeviceNS(os("COM1")))
+            );
+            assert_eq!(classify(r"hello\world"), None);
+        }
+
+        #[test]
+        fn device_namespaces() {
+            assert_eq!(classify(r"\\.\pipe\name"), Some(PrefixKind::Pipe));
+            assert_eq!(
+                classify(r"\\.\MAILSLOT\box\name"),
+                Some(PrefixKind::Mailslot)
+            );
+            assert_eq!(
+                classify(r"\\.\PhysicalDrive12"),
+                Some(PrefixKind::PhysicalDrive(12))
+            );
+            assert_eq!(
+                classify(r"\\.\PhysicalDriveX"),
+                Some(PrefixKind::DeviceNS(OsStr::new("PhysicalDriveX")))
+            );
+        }
+
+        #[test]
+        fn volume() {
+            let path = r"\\?\Volume{26a21bda-a627-11d7-9931-806e6f6e6963}\a";
+            let guid = OsStr::new("26a21bda-a627-11d7-9931-806e6f6e6963");
+            assert_eq!(classify(path), Some(PrefixKind::Volume(guid)));
+        }
+
+        #[test]
+        fn globalroot() {
+            let path = r"\\?\GLOBALROOT\Device\HarddiskVolume1\a";
+            let object = OsStr::new(r"Device\HarddiskVolume1");
+            assert_eq!(classify(path), Some(PrefixKind::GlobalRoot(object)));
+        }
+
+        #[test]
+        fn nt_object() {
+            let os = OsStr::new;
+            assert_eq!(
+                classify(r"\??\C:\hello"),
+                Some(PrefixKind::NtObject(os("C:")))
+            );
+            assert_eq!(
+                classify(r"\??\UNC\server\share\hello"),
+                Some(PrefixKind::NtObject(os(r"UNC\server\share")))
+            );
+        }
+    }
 }
 
 mod iter {
     use super::*;
 
     #[test]
-    fn verbatim_disk<'path>() {
+    fn verbatim_disk() {
         let path = br"\\?\C:\hello";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 3);
 
-        let expected: Vec<PathComponent<'path>> = vec![
+        let expected: Vec<PathComponent> = vec![
             Ok(Component::Prefix(PrefixComponent::new(
                 br"\\?\C:\",
                 Prefix::VerbatimDisk(b'C'),
@@ -153,14 +1114,14 @@ mod iter {
     }
 
     #[test]
-    fn prefix_noroot<'path>() {
+    fn prefix_noroot() {
         let path = br"C:";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 1);
 
-        let expected: Vec<PathComponent<'path>> = vec![Ok(Component::Prefix(
+        let expected: Vec<PathComponent> = vec![Ok(Component::Prefix(
             PrefixComponent::new(br"C:", Prefix::Disk(b'C')),
         ))];
 
@@ -168,14 +1129,14 @@ mod iter {
     }
 
     #[test]
-    fn invalid_char<'path>() {
+    fn invalid_char() {
         let path = br"C:\hello.";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 3);
 
-        let expected_ok: Vec<PathComponent<'path>> = vec![
+        let expected_ok: Vec<PathComponent> = vec![
             Ok(Component::Prefix(PrefixComponent::new(
                 br"C:",
                 Prefix::Disk(b'C'),
@@ -188,26 +1149,24 @@ mod iter {
         // Check last element is an error
         let result = match &comp[2] {
             Ok(_) => false,
-            Err(e) => match e.kind() {
-                ParseErrorKind::Windows(WindowsErrorKind::InvalidCharacter) => {
-                    true
-                }
-                _ => false,
-            },
+            Err(e) => matches!(
+                e.kind(),
+                ParseErrorKind::Windows(WindowsErrorKind::InvalidCharacter)
+            ),
         };
 
         assert!(result);
     }
 
     #[test]
-    fn verbatim_path<'path>() {
+    fn verbatim_path() {
         let path = br"\\?\hello\world";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 3);
 
-        let expected: Vec<PathComponent<'path>> = vec![
+        let expected: Vec<PathComponent> = vec![
             Ok(Component::Prefix(PrefixComponent::new(
                 br"\\?\hello",
                 Prefix::Verbatim(OsStr::new(r"hello")),
@@ -220,12 +1179,12 @@ mod iter {
     }
 
     #[test]
-    fn invalid_filename<'path>() {
+    fn invalid_filename() {
         // --------------------
         // GIVEN
         // --------------------
         // an absolute path with a file using a restricted name
-        let path = br"\\?\hello\nul.txt";
+        let path = br"\\server\share\nul.txt";
 
         // --------------------
         // WHEN
@@ -245,10 +1204,10 @@ mod iter {
         // the error is a ParseErrorKind::RestrictedName kind
         assert_eq!(comp.len(), 3);
 
-        let expected_ok: Vec<PathComponent<'path>> = vec![
+        let expected_ok: Vec<PathComponent> = vec![
             Ok(Component::Prefix(PrefixComponent::new(
-                br"\\?\hello",
-                Prefix::Verbatim(OsStr::new(r"hello")),
+                br"\\server\share",
+                Prefix::UNC(OsStr::new("server"), OsStr::new("share")),
             ))),
             Ok(Component::RootDir(OsStr::new(r"\"))),
         ];
@@ -258,26 +1217,24 @@ mod iter {
         // Check last element is an error
         let result = match &comp[2] {
             Ok(_) => false,
-            Err(e) => match e.kind() {
-                ParseErrorKind::Windows(WindowsErrorKind::RestrictedName) => {
-                    true
-                }
-                _ => false,
-            },
+            Err(e) => matches!(
+                e.kind(),
+                ParseErrorKind::Windows(WindowsErrorKind::RestrictedName)
+            ),
         };
 
         assert!(result);
     }
 
     #[test]
-    fn relative_path<'path>() {
+    fn relative_path() {
         let path = br"hello\world";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 2);
 
-        let expected: Vec<PathComponent<'path>> = vec![
+        let expected: Vec<PathComponent> = vec![
             Ok(Component::Normal(OsStr::new(r"hello"))),
             Ok(Component::Normal(OsStr::new(r"world"))),
         ];
@@ -286,14 +1243,14 @@ mod iter {
     }
 
     #[test]
-    fn double_path_separator<'path>() {
+    fn double_path_separator() {
         let path = br"hello\\world";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 3);
 
-        let expected: Vec<PathComponent<'path>> = vec![
+        let expected: Vec<PathComponent> = vec![
             Ok(Component::Normal(OsStr::new(r"hello"))),
             Ok(Component::CurDir),
             Ok(Component::Normal(OsStr::new(r"world"))),
@@ -303,14 +1260,14 @@ mod iter {
     }
 
     #[test]
-    fn curdir<'path>() {
+    fn curdir() {
         let path = br"hello\world\.\what\now";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 5);
 
-        let expected: Vec<PathComponent<'path>> = vec![
+        let expected: Vec<PathComponent> = vec![
             Ok(Component::Normal(OsStr::new(r"hello"))),
             Ok(Component::Normal(OsStr::new(r"world"))),
             Ok(Component::CurDir),
@@ -322,14 +1279,14 @@ mod iter {
     }
 
     #[test]
-    fn parentdir<'path>() {
+    fn parentdir() {
         let path = br"hello\world\..\what\now";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 5);
 
-        let expected: Vec<PathComponent<'path>> = vec![
+        let expected: Vec<PathComponent> = vec![
             Ok(Component::Normal(OsStr::new(r"hello"))),
             Ok(Component::Normal(OsStr::new(r"world"))),
             Ok(Component::ParentDir),
@@ -341,14 +1298,14 @@ mod iter {
     }
 
     #[test]
-    fn curdir_at_start<'path>() {
+    fn curdir_at_start() {
         let path = br".\hello\world";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 3);
 
-        let expected: Vec<PathComponent<'path>> = vec![
+        let expected: Vec<PathComponent> = vec![
             Ok(Component::CurDir),
             Ok(Component::Normal(OsStr::new(r"hello"))),
             Ok(Component::Normal(OsStr::new(r"world"))),
@@ -358,14 +1315,14 @@ mod iter {
     }
 
     #[test]
-    fn parentdir_at_start<'path>() {
+    fn parentdir_at_start() {
         let path = br"..\hello\world\";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 3);
 
-        let expected: Vec<PathComponent<'path>> = vec![
+        let expected: Vec<PathComponent> = vec![
             Ok(Component::ParentDir),
             Ok(Component::Normal(OsStr::new(r"hello"))),
             Ok(Component::Normal(OsStr::new(r"world"))),
@@ -375,14 +1332,14 @@ mod iter {
     }
 
     #[test]
-    fn mixed_separator<'path>() {
+    fn mixed_separator() {
         let path = br"hello\world/what\now/brown/cow";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
         assert_eq!(comp.len(), 6);
 
-        let expected: Vec<PathComponent<'path>> = vec![
+        let expected: Vec<PathComponent> = vec![
             Ok(Component::Normal(OsStr::new(r"hello"))),
             Ok(Component::Normal(OsStr::new(r"world"))),
             Ok(Component::Normal(OsStr::new(r"what"))),
@@ -395,15 +1352,654 @@ mod iter {
     }
 
     #[test]
-    fn empty_path<'path>() {
+    fn empty_path() {
         let path = b"";
         let iter = Iter::new(SystemStr::from_bytes(path));
 
         let comp: Vec<PathComponent> = iter.collect();
-        let expected: Vec<PathComponent<'path>> = vec![Ok(Component::CurDir)];
+        let expected: Vec<PathComponent> = vec![Ok(Component::CurDir)];
 
         assert_eq!(comp, expected);
     }
+
+    fn reversed(path: &[u8]) -> Vec<PathComponent<'_>> {
+        let mut comp: Vec<PathComponent> =
+            Iter::new(SystemStr::from_bytes(path)).rev().collect();
+        comp.reverse();
+        comp
+    }
+
+    #[test]
+    fn next_back_matches_next() {
+        let paths: Vec<&[u8]> = vec![
+            b"",
+            br"\",
+            br"\\",
+            b".",
+            br"hello",
+            br"hello\",
+            br"\hello\world\",
+            br"hello\\world",
+            br"hello\world/..\what\.\now",
+            br"C:",
+            br"C:hello\world",
+            br"C:\",
+            br"C:\hello\world",
+            br"\\server\share",
+            br"\\server\share\hello\",
+            br"\\?\C:\",
+            br"\\?\C:\hello",
+            br"\\?\hello\world",
+            br"\\?\UNC\server\share\hello",
+            br"\\.\COM1\hello",
+        ];
+
+        for path in paths {
+            let forward: Vec<PathComponent> =
+                Iter::new(SystemStr::from_bytes(path)).collect();
+            assert_eq!(reversed(path), forward);
+        }
+    }
+
+    #[test]
+    fn next_back_mixed_with_next() {
+        let path = br"C:\hello\world";
+        let mut iter = Iter::new(SystemStr::from_bytes(path));
+
+        assert_eq!(
+            iter.next_back(),
+            Some(Ok(Component::Normal(OsStr::new("world"))))
+        );
+        assert_eq!(
+            iter.next(),
+            Some(Ok(Component::Prefix(PrefixComponent::new(
+                br"C:",
+                Prefix::Disk(b'C'),
+            ))))
+        );
+        assert_eq!(
+            iter.next_back(),
+            Some(Ok(Component::Normal(OsStr::new("hello"))))
+        );
+        assert_eq!(
+            iter.next_back(),
+            Some(Ok(Component::RootDir(OsStr::new(r"\"))))
+        );
+        assert_eq!(iter.next_back(), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn next_back_prefix_is_last() {
+        let path = br"\\server\share\hello";
+        let mut iter = Iter::new(SystemStr::from_bytes(path));
+
+        assert_eq!(
+            iter.next_back(),
+            Some(Ok(Component::Normal(OsStr::new("hello"))))
+        );
+        assert_eq!(
+            iter.next_back(),
+            Some(Ok(Component::RootDir(OsStr::new(r"\"))))
+        );
+        assert_eq!(
+            iter.next_back(),
+            Some(Ok(Component::Prefix(PrefixComponent::new(
+                br"\\server\share",
+                Prefix::UNC(OsStr::new("server"), OsStr::new("share")),
+            ))))
+        );
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn next_back_invalid_filename() {
+        let path = br"C:\hello\nul.txt\world";
+        let forward: Vec<PathComponent> =
+            Iter::new(SystemStr::from_bytes(path)).collect();
+
+        let mut iter = Iter::new(SystemStr::from_bytes(path));
+        assert_eq!(
+            iter.next_back(),
+            Some(Ok(Component::Normal(OsStr::new("world"))))
+        );
+
+        // The error is the same as the one found from the front, and it ends
+        // the iteration
+        let err = iter.next_back();
+        assert_eq!(err.as_ref(), forward.get(3));
+        assert!(err.unwrap().is_err());
+        assert_eq!(iter.next_back(), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    fn spans(path: &[u8]) -> Vec<(std::ops::Range<usize>, Component<'_>)> {
+        Iter::new(SystemStr::from_bytes(path))
+            .spans()
+            .map(|s| s.unwrap())
+            .collect()
+    }
+
+    fn spans_back(path: &[u8]) -> Vec<(std::ops::Range<usize>, Component<'_>)> {
+        let mut ret: Vec<_> = Iter::new(SystemStr::from_bytes(path))
+            .spans()
+            .rev()
+            .map(|s| s.unwrap())
+            .collect();
+        ret.reverse();
+        ret
+    }
+
+    #[test]
+    fn spans_disk() {
+        let path = br"C:\hello\\world";
+        let expected = vec![
+            (
+                0..2,
+                Component::Prefix(PrefixComponent::new(
+                    br"C:",
+                    Prefix::Disk(b'C'),
+                )),
+            ),
+            (2..3, Component::RootDir(OsStr::new(r"\"))),
+            (3..8, Component::Normal(OsStr::new("hello"))),
+            (9..9, Component::CurDir),
+            (10..15, Component::Normal(OsStr::new("world"))),
+        ];
+
+        assert_eq!(spans(path), expected);
+        assert_eq!(spans_back(path), expected);
+    }
+
+    #[test]
+    fn spans_verbatim_disk() {
+        // The root is the last byte of the prefix
+        let path = br"\\?\C:\hello";
+        let expected = vec![
+            (
+                0..7,
+                Component::Prefix(PrefixComponent::new(
+                    br"\\?\C:\",
+                    Prefix::VerbatimDisk(b'C'),
+                )),
+            ),
+            (6..7, Component::RootDir(OsStr::new(r"\"))),
+            (7..12, Component::Normal(OsStr::new("hello"))),
+        ];
+
+        assert_eq!(spans(path), expected);
+        assert_eq!(spans_back(path), expected);
+    }
+
+    #[test]
+    fn spans_unc() {
+        let path = br"\\server\share\hello";
+        let expected = vec![
+            (
+                0..14,
+                Component::Prefix(PrefixComponent::new(
+                    br"\\server\share",
+                    Prefix::UNC(OsStr::new("server"), OsStr::new("share")),
+                )),
+            ),
+            (14..15, Component::RootDir(OsStr::new(r"\"))),
+            (15..20, Component::Normal(OsStr::new("hello"))),
+        ];
+
+        assert_eq!(spans(path), expected);
+        assert_eq!(spans_back(path), expected);
+    }
+
+    #[test]
+    fn spans_invalid_filename() {
+        let path = br"C:\nul.txt";
+        let spans: Vec<_> =
+            Iter::new(SystemStr::from_bytes(path)).spans().collect();
+
+        assert_eq!(spans.len(), 3);
+        match &spans[2] {
+            Err(e) => assert_eq!(
+                e.kind(),
+                ParseErrorKind::Windows(WindowsErrorKind::RestrictedName)
+            ),
+            Ok(_) => panic!("expected an error"),
+        }
+    }
+
+    fn rebuild(path: &str) -> OsString {
+        let mut ret = OsString::new();
+        for token in WindowsPath::new(path).tokens() {
+            ret.push(token.as_os_str());
+        }
+        ret
+    }
+
+    #[test]
+    fn tokens_rebuild_path() {
+        let paths = vec![
+            "",
+            r"\",
+            r"C:",
+            r"C:a/b",
+            r"C:\a\\b/",
+            r"\\server\share",
+            r"\\server\share\a/b",
+            r"\\?\C:\",
+            r"\\?\C:\a",
+            r"\\?\C:\\a",
+            r"\\?\a\b",
+            r"\\?\UNC\server\share\a",
+            r"\\.\COM1\a",
+            r"a\\\b//",
+        ];
+
+        for path in paths {
+            assert_eq!(rebuild(path), OsString::from(path));
+        }
+    }
+
+    #[test]
+    fn tokens_separators() {
+        let tokens: Vec<Token> =
+            WindowsPath::new(r"C:\hello/\world").tokens().collect();
+        let expected = vec![
+            Token::Prefix(OsStr::new("C:")),
+            Token::RootDir(OsStr::new(r"\")),
+            Token::Normal(OsStr::new("hello")),
+            Token::Separator(OsStr::new("/")),
+            Token::Empty,
+            Token::Separator(OsStr::new(r"\")),
+            Token::Normal(OsStr::new("world")),
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn tokens_verbatim_disk() {
+        // The separator that ends the prefix is only given once
+        let tokens: Vec<Token> =
+            WindowsPath::new(r"\\?\C:\hello").tokens().collect();
+        let expected = vec![
+            Token::Prefix(OsStr::new(r"\\?\C:")),
+            Token::RootDir(OsStr::new(r"\")),
+            Token::Normal(OsStr::new("hello")),
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn tokens_skip_validation() {
+        let tokens: Vec<Token> =
+            WindowsPath::new(r"a\nul.txt").tokens().collect();
+        let expected = vec![
+            Token::Normal(OsStr::new("a")),
+            Token::Separator(OsStr::new(r"\")),
+            Token::Normal(OsStr::new("nul.txt")),
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn recovering() {
+        let path = br"C:\hello.\world\nul\what<\now";
+        let comp: Vec<PathComponent> = Iter::new(SystemStr::from_bytes(path))
+            .recovering()
+            .collect();
+
+        assert_eq!(comp.len(), 7);
+        assert_eq!(comp[3], Ok(Component::Normal(OsStr::new("world"))));
+        assert_eq!(comp[6], Ok(Component::Normal(OsStr::new("now"))));
+
+        let kinds: Vec<WindowsErrorKind> = comp
+            .iter()
+            .filter_map(|c| match c {
+                Err(e) => match e.kind() {
+                    ParseErrorKind::Windows(kind) => Some(kind),
+                    _ => None,
+                },
+                Ok(_) => None,
+            })
+            .collect();
+        let expected = vec![
+            WindowsErrorKind::InvalidCharacter,
+            WindowsErrorKind::RestrictedName,
+            WindowsErrorKind::InvalidCharacter,
+        ];
+        assert_eq!(kinds, expected);
+
+        // Every component is still returned from the back
+        let mut back: Vec<PathComponent> =
+            Iter::new(SystemStr::from_bytes(path))
+                .recovering()
+                .rev()
+                .collect();
+        back.reverse();
+        assert_eq!(back, comp);
+    }
+
+    #[test]
+    fn recovering_stops_by_default() {
+        let path = br"C:\hello.\world";
+        let comp: Vec<PathComponent> =
+            Iter::new(SystemStr::from_bytes(path)).collect();
+
+        assert_eq!(comp.len(), 3);
+        assert!(comp[2].is_err());
+    }
+
+    fn components(path: &str) -> Vec<PathComponent<'_>> {
+        Iter::new(SystemStr::new(path)).collect()
+    }
+
+    #[test]
+    fn reserved_names() {
+        let names = vec![
+            "CONIN$",
+            "conout$.txt",
+            "COM0",
+            "LPT0.log",
+            "COM¹",
+            "com².txt",
+            "LPT³",
+            "NUL .txt",
+            "aux.tar.gz",
+        ];
+        let restricted =
+            ParseErrorKind::Windows(WindowsErrorKind::RestrictedName);
+        for name in names {
+            let path = format!(r"C:\dir\{}", name);
+            let comp = components(&path);
+            let err = comp.last().unwrap().as_ref().unwrap_err();
+            assert_eq!(err.kind(), restricted, "{}", name);
+        }
+
+        let comp = components(r"C:\dir\COM10");
+        assert_eq!(comp[3], Ok(Component::Normal(OsStr::new("COM10"))));
+    }
+
+    #[test]
+    fn with_streams() {
+        let path = r"C:\dl\file.txt:Zone.Identifier:$DATA";
+        let comp: Vec<PathComponent> =
+            Iter::new(SystemStr::new(path)).with_streams().collect();
+        let last = OsStr::new("file.txt:Zone.Identifier:$DATA");
+        assert_eq!(comp.len(), 4);
+        assert_eq!(comp[3], Ok(Component::Normal(last)));
+
+        let mut back = Iter::new(SystemStr::new(path)).with_streams();
+        assert_eq!(back.next_back(), Some(Ok(Component::Normal(last))));
+
+        let spans: Vec<_> = Iter::new(SystemStr::new(path))
+            .with_streams()
+            .spans()
+            .collect();
+        assert_eq!(spans[3], Ok((6..path.len(), Component::Normal(last))));
+    }
+
+    #[test]
+    fn with_streams_errors() {
+        let stream_err = |path: &str| {
+            let comp: Vec<PathComponent> =
+                Iter::new(SystemStr::new(path)).with_streams().collect();
+            match comp.last() {
+                Some(Err(e)) => Some((e.kind(), e.span())),
+                _ => None,
+            }
+        };
+        let invalid_char =
+            ParseErrorKind::Windows(WindowsErrorKind::InvalidCharacter);
+        let restricted =
+            ParseErrorKind::Windows(WindowsErrorKind::RestrictedName);
+
+        // A stream is only allowed in the last component
+        assert_eq!(stream_err(r"x\ab:s\b").map(|e| e.0), Some(invalid_char));
+        assert_eq!(stream_err(r"x\a:s:DATA"), Some((invalid_char, 3..10)));
+        assert_eq!(stream_err(r"x\a<:s").map(|e| e.0), Some(invalid_char));
+        assert_eq!(stream_err(r"x\con:s").map(|e| e.0), Some(restricted));
+    }
+
+    #[test]
+    fn verbatim_disk_literal_names() {
+        // After a verbatim prefix, / is not a separator and . and .. are not
+        // interpreted
+        let expected: Vec<PathComponent> = vec![
+            Ok(Component::Prefix(PrefixComponent::new(
+                br"\\?\C:\",
+                Prefix::VerbatimDisk(b'C'),
+            ))),
+            Ok(Component::RootDir(OsStr::new(r"\"))),
+            Ok(Component::Normal(OsStr::new("a/b"))),
+            Ok(Component::Normal(OsStr::new("."))),
+            Ok(Component::Normal(OsStr::new(".."))),
+            Ok(Component::Normal(OsStr::new("c"))),
+        ];
+
+        let path = r"\\?\C:\a/b\.\..\c";
+        assert_eq!(components(path), expected);
+        assert_eq!(reversed(path.as_bytes()), expected);
+    }
+
+    #[test]
+    fn verbatim_unc_literal_names() {
+        let expected: Vec<PathComponent> = vec![
+            Ok(Component::Prefix(PrefixComponent::new(
+                br"\\?\UNC\server\share",
+                Prefix::VerbatimUNC(OsStr::new("server"), OsStr::new("share")),
+            ))),
+            Ok(Component::RootDir(OsStr::new(r"\"))),
+            Ok(Component::Normal(OsStr::new("."))),
+            Ok(Component::Normal(OsStr::new("a/b/"))),
+        ];
+
+        let path = r"\\?\UNC\server\share\.\a/b/";
+        assert_eq!(components(path), expected);
+        assert_eq!(reversed(path.as_bytes()), expected);
+    }
+
+    #[test]
+    fn invalid_unc_host() {
+        let paths = vec![
+            (r"\\bad host\share\x", "bad host"),
+            (r"//-bad.example.com/share", "-bad.example.com"),
+        ];
+        for (path, host) in paths {
+            let comp = components(path);
+            assert_eq!(comp.len(), 1, "{}", path);
+            let err = match &comp[0] {
+                Err(e) => e,
+                Ok(_) => panic!("expected an error for {}", path),
+            };
+            assert_eq!(
+                err.kind(),
+                ParseErrorKind::Windows(WindowsErrorKind::InvalidHost)
+            );
+            assert_eq!(err.component(), OsStr::new(host));
+            assert_eq!(err.span(), 2..2 + host.len());
+
+            assert_eq!(reversed(path.as_bytes()), comp);
+            assert_eq!(WindowsPath::new(path).validate_all().len(), 1);
+        }
+
+        // NetBIOS names can hold punctuation
+        let expected = Ok(Component::Prefix(PrefixComponent::new(
+            br"\\acme!#'\share",
+            Prefix::UNC(OsStr::new("acme!#'"), OsStr::new("share")),
+        )));
+        assert_eq!(components(r"\\acme!#'\share\x")[0], expected);
+    }
+
+    #[test]
+    fn verbatim_allows_reserved_names() {
+        // Windows hands a verbatim path to the file system unchanged, so
+        // reserved names and a trailing dot or space are ordinary names
+        let paths = vec![
+            (r"\\?\C:\CON", vec!["CON"]),
+            (r"\\?\C:\foo.", vec!["foo."]),
+            (r"\\?\C:\a.\..", vec!["a.", ".."]),
+            (r"\\?\C:\a/b\nul.txt ", vec!["a/b", "nul.txt "]),
+        ];
+        for (path, names) in paths {
+            let mut expected: Vec<PathComponent> = vec![
+                Ok(Component::Prefix(PrefixComponent::new(
+                    br"\\?\C:\",
+                    Prefix::VerbatimDisk(b'C'),
+                ))),
+                Ok(Component::RootDir(OsStr::new(r"\"))),
+            ];
+            for name in names {
+                expected.push(Ok(Component::Normal(OsStr::new(name))));
+            }
+
+            assert_eq!(components(path), expected, "{}", path);
+            assert_eq!(reversed(path.as_bytes()), expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn verbatim_rejects_restricted_chars() {
+        let comp = components(r"\\?\C:\a/b\c<d");
+        match &comp[3] {
+            Err(e) => assert_eq!(
+                e.kind(),
+                ParseErrorKind::Windows(WindowsErrorKind::InvalidCharacter)
+            ),
+            Ok(_) => panic!("expected an error"),
+        }
+    }
+
+    #[test]
+    fn nonverbatim_normalizes() {
+        let expected: Vec<PathComponent> = vec![
+            Ok(Component::Prefix(PrefixComponent::new(
+                br"C:",
+                Prefix::Disk(b'C'),
+            ))),
+            Ok(Component::RootDir(OsStr::new(r"\"))),
+            Ok(Component::Normal(OsStr::new("a"))),
+            Ok(Component::Normal(OsStr::new("b"))),
+            Ok(Component::CurDir),
+            Ok(Component::ParentDir),
+        ];
+
+        assert_eq!(components(r"C:\a/b\.\.."), expected);
+    }
+
+    #[test]
+    fn verbatim_tokens() {
+        let tokens: Vec<Token> =
+            WindowsPath::new(r"\\?\C:\a/b\c").tokens().collect();
+        let expected = vec![
+            Token::Prefix(OsStr::new(r"\\?\C:")),
+            Token::RootDir(OsStr::new(r"\")),
+            Token::Normal(OsStr::new("a/b")),
+            Token::Separator(OsStr::new(r"\")),
+            Token::Normal(OsStr::new("c")),
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn verbatim_parts() {
+        use crate::path::Path;
+
+        let path = WindowsPath::new(r"\\?\C:\a/b\..");
+        let parts: Vec<OsString> = path.parts().collect();
+        let expected = vec![
+            OsString::from(r"\\?\C:\"),
+            OsString::from("a/b"),
+            OsString::from(".."),
+        ];
+        assert_eq!(parts, expected);
+
+        let path = WindowsPath::new(r"\\?\UNC\server\share\a/b");
+        let parts: Vec<OsString> = path.parts().collect();
+        let expected = vec![
+            OsString::from(r"\\?\UNC\server\share\"),
+            OsString::from("a/b"),
+        ];
+        assert_eq!(parts, expected);
+    }
+
+    #[test]
+    fn unc_host_and_share() {
+        let prefix = |path: &'static str| match components(path).remove(0) {
+            Ok(Component::Prefix(prefix)) => Some(prefix),
+            _ => None,
+        };
+
+        let unc = prefix(r"\\fe80--1s4.ipv6-literal.net\C$\x").unwrap();
+        let expected = UncHost::Ipv6 {
+            addr: "fe80::1".parse().unwrap(),
+            zone: Some(OsStr::new("4")),
+        };
+        assert_eq!(unc.unc_host(), Some(expected));
+        assert_eq!(unc.unc_share(), Some(UncShare::Disk(b'C')));
+
+        let unc = prefix(r"\\?\UNC\files.example.com\IPC$").unwrap();
+        let host = UncHost::Name(OsStr::new("files.example.com"));
+        assert_eq!(unc.unc_host(), Some(host));
+        assert_eq!(unc.unc_share(), Some(UncShare::Ipc));
+
+        let disk = prefix(r"C:\x").unwrap();
+        assert_eq!(disk.unc_host(), None);
+        assert_eq!(disk.unc_share(), None);
+
+        // A malformed host does not start a UNC prefix
+        assert_eq!(prefix(r"\\bad host\share\x"), None);
+        assert_eq!(prefix(r"\\999.1.1.1\share\x"), None);
+    }
+
+    #[test]
+    fn object_prefixes() {
+        let expected: Vec<PathComponent> = vec![
+            Ok(Component::Prefix(PrefixComponent::new(
+                br"\\?\GLOBALROOT\Device\HarddiskVolume1",
+                Prefix::Verbatim(OsStr::new("GLOBALROOT")),
+            ))),
+            Ok(Component::RootDir(OsStr::new(r"\"))),
+            Ok(Component::Normal(OsStr::new("a"))),
+        ];
+        let path = r"\\?\GLOBALROOT\Device\HarddiskVolume1\a";
+        assert_eq!(components(path), expected);
+        assert_eq!(reversed(path.as_bytes()), expected);
+
+        let expected: Vec<PathComponent> = vec![
+            Ok(Component::Prefix(PrefixComponent::new(
+                br"\??\C:\",
+                Prefix::VerbatimDisk(b'C'),
+            ))),
+            Ok(Component::RootDir(OsStr::new(r"\"))),
+            Ok(Component::Normal(OsStr::new("a/b"))),
+        ];
+        let path = r"\??\C:\a/b";
+        assert_eq!(components(path), expected);
+        assert_eq!(reversed(path.as_bytes()), expected);
+
+        let expected: Vec<PathComponent> = vec![
+            Ok(Component::Prefix(PrefixComponent::new(
+                br"\??\UNC\server\share",
+                Prefix::VerbatimUNC(OsStr::new("server"), OsStr::new("share")),
+            ))),
+            Ok(Component::RootDir(OsStr::new(r"\"))),
+            Ok(Component::Normal(OsStr::new("a"))),
+        ];
+        let path = r"\??\UNC\server\share\a";
+        assert_eq!(components(path), expected);
+        assert_eq!(reversed(path.as_bytes()), expected);
+    }
+
+    #[test]
+    fn verbatim_parent() {
+        use crate::path::SystemSeq;
+
+        let path = WindowsPath::new(r"\\?\C:\a\b/c");
+        let parent = path.parent().map(|p| p.as_os_str());
+        assert_eq!(parent, Some(OsStr::new(r"\\?\C:\a")));
+    }
 }
 
 // ===========================================================================
@@ -24,16 +24,24 @@ use std::ops::Deref;
 // Third-party imports
 
 // Local imports
+use self::path_type::Separator;
+use crate::common::error::{
+    ConvertError, ParseError, RelativePathError, SuffixError,
+};
+use crate::common::string::{as_osstr, offset_in};
 use crate::path::{
-    AsSystemStr, Path, PathBuf, PathParts, PathPartsExt as _, SystemStr,
-    SystemString,
+    AsSystemStr, Path, PathBuf, PathItem, PathIterator, PathParts,
+    PathPartsExt as _, SeparatorDisplay, SystemSeq, SystemStr, SystemString,
 };
+use crate::windows::{self, ConvertPolicy, WindowsPathBuf};
 
 // ===========================================================================
 // Re-exports
 // ===========================================================================
 
-pub use self::iter::{Component, Iter, PathComponent};
+pub use self::iter::{
+    Component, Iter, PathComponent, SpannedComponent, Spans, Token, Tokens,
+};
 
 // ===========================================================================
 // Types needed for Iter
@@ -42,6 +50,7 @@ pub use self::iter::{Component, Iter, PathComponent};
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UnixErrorKind {
     InvalidCharacter,
+    RestrictedName,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
@@ -52,6 +61,195 @@ enum PathParseState {
     Finish,
 }
 
+// What next_back() parses next, which is the reverse of the order that
+// PathParseState goes through
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+enum BackParseState {
+    PathComponent,
+    Root,
+}
+
+// ===========================================================================
+// Helpers
+// ===========================================================================
+
+// End of the components that follow the root, for an Iter that can parse
+// them from either end. Every component is treated as being followed by a
+// separator, so a path that does not end in one gets a virtual separator
+// just past its last byte.
+fn components_end(path: &[u8], start: usize) -> usize {
+    match path.last() {
+        Some(_) if start == path.len() => start,
+        Some(&last) if Separator == last => path.len(),
+        _ => path.len() + 1,
+    }
+}
+
+// Number of bytes of path that Iter consumes as the root component
+fn root_len(path: &[u8]) -> usize {
+    let mut iter = Iter::new(SystemStr::from_bytes(path));
+    match iter.next() {
+        Some(Ok(Component::RootDir)) => {
+            let rest: &SystemStr = iter.as_ref();
+            path.len() - rest.as_bytes().len()
+        }
+        _ => 0,
+    }
+}
+
+// Length of the path that remains once the last component is removed, or
+// None if there is no component after the root to remove
+fn parent_len(path: &[u8]) -> Option<usize> {
+    let root_end = root_len(path);
+    let rest = &path[root_end..];
+
+    // Trailing separators are not part of the last component
+    let comp_end = rest.iter().rposition(|&b| Separator != b)? + 1;
+    let comp_start = rest[..comp_end]
+        .iter()
+        .rposition(|&b| Separator == b)
+        .map_or(0, |i| i + 1);
+    let parent_end = rest[..comp_start]
+        .iter()
+        .rposition(|&b| Separator != b)
+        .map_or(0, |i| i + 1);
+
+    Some(root_end + parent_end)
+}
+
+// Checks that name would be parsed by Iter as a single normal component,
+// so that it can be used as the file name of a path
+fn validate_file_name(name: &SystemStr) -> Result<(), ParseError> {
+    let bytes = name.as_bytes();
+    if let Some(i) = bytes.iter().position(|&b| Separator == b) {
+        let msg = String::from("file name contains a path separator");
+        return Err(file_name_error(
+            UnixErrorKind::InvalidCharacter,
+            bytes,
+            i,
+            i + 1,
+            msg,
+        ));
+    }
+
+    match Iter::new(name).next() {
+        Some(Ok(Component::Normal(_))) => Ok(()),
+        Some(Err(e)) => Err(e),
+        _ => {
+            let msg = String::from("file name uses a restricted name");
+            Err(file_name_error(
+                UnixErrorKind::RestrictedName,
+                bytes,
+                0,
+                bytes.len(),
+                msg,
+            ))
+        }
+    }
+}
+
+fn file_name_error(
+    kind: UnixErrorKind,
+    name: &[u8],
+    start: usize,
+    end: usize,
+    msg: String,
+) -> ParseError {
+    ParseError::new(
+        kind.into(),
+        as_osstr(&name[start..end]).to_os_string(),
+        as_osstr(name).to_os_string(),
+        start,
+        end,
+        msg,
+    )
+}
+
+// The root and remaining components of a lexically normalized path
+struct Normalized<'path> {
+    has_root: bool,
+    comps: Vec<Component<'path>>,
+}
+
+impl<'path> Normalized<'path> {
+    fn new(path: &'path SystemStr) -> Result<Normalized<'path>, ParseError> {
+        let mut has_root = false;
+        let mut comps: Vec<Component> = Vec::new();
+        for comp in Iter::new(path) {
+            match comp? {
+                Component::RootDir => has_root = true,
+                Component::CurDir => {}
+                Component::ParentDir => match comps.last() {
+                    Some(Component::Normal(_)) => {
+                        comps.pop();
+                    }
+                    _ if has_root => {}
+                    _ => comps.push(Component::ParentDir),
+                },
+                comp @ Component::Normal(_) => comps.push(comp),
+            }
+        }
+
+        Ok(Normalized { has_root, comps })
+    }
+
+    fn to_pathbuf(&self, capacity: usize) -> UnixPathBuf {
+        let mut pathbuf = UnixPathBuf::with_capacity(capacity);
+        if self.has_root {
+            pathbuf.pathbuf.push("/");
+        }
+        for (i, comp) in self.comps.iter().enumerate() {
+            if i > 0 {
+                pathbuf.pathbuf.push("/");
+            }
+            pathbuf.pathbuf.push(comp.as_os_str());
+        }
+        if pathbuf.as_bytes().is_empty() {
+            pathbuf.pathbuf.push(".");
+        }
+        pathbuf
+    }
+}
+
+impl<'path> PathItem<'path> for PathComponent<'path> {
+    fn is_curdir(&self) -> bool {
+        matches!(self, Ok(Component::CurDir))
+    }
+
+    fn normal(&self) -> Option<&'path OsStr> {
+        match self {
+            Ok(Component::Normal(name)) => Some(name),
+            _ => None,
+        }
+    }
+
+    fn is_root(&self) -> bool {
+        matches!(self, Ok(Component::RootDir))
+    }
+
+    fn same_component(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    // A suffix is a dot followed by at least one character, and never
+    // starts the name (eg .bashrc has no suffix)
+    fn suffix_start(name: &[u8]) -> Option<usize> {
+        let start = name.iter().rposition(|&b| b == b'.')?;
+        if start == 0 || start == name.len() - 1 {
+            None
+        } else {
+            Some(start)
+        }
+    }
+
+    fn is_separator(byte: u8) -> bool {
+        Separator == byte
+    }
+}
+
 // ===========================================================================
 // SystemStr types
 // ===========================================================================
@@ -76,6 +274,103 @@ impl UnixPath {
     }
 }
 
+impl UnixPath {
+    // The path without its last component, or None if there is no component
+    // after the root to remove
+    pub fn parent(&self) -> Option<&UnixPath> {
+        let path = self.as_bytes();
+        let len = parent_len(path)?;
+        Some(UnixPath::new(SystemStr::from_bytes(&path[..len])))
+    }
+
+    pub fn ancestors(&self) -> Ancestors<'_> {
+        Ancestors { next: Some(self) }
+    }
+
+    // The path split up as it was written, separators included
+    pub fn tokens(&self) -> Tokens<'_> {
+        Tokens::new(self)
+    }
+
+    // Every invalid component of the path, in order. Each error records the
+    // range of the path that it covers.
+    pub fn validate_all(&self) -> Vec<ParseError> {
+        Iter::new(self)
+            .recovering()
+            .filter_map(Result::err)
+            .collect()
+    }
+
+    // Shows the path with \ as its separator, eg dir/file as dir\file.
+    // Names are shown as they are, so this is not a conversion to a Windows
+    // path (see to_windows()).
+    pub fn as_windows(&self) -> SeparatorDisplay<'_> {
+        SeparatorDisplay::new(self.as_bytes(), |b| Separator == b, b'\\')
+    }
+
+    // The same path as a Windows one, eg /dir/file becomes C:\dir\file when
+    // policy maps / to C:\. Names that Windows does not allow are escaped
+    // or rejected as the policy says.
+    pub fn to_windows(
+        &self,
+        policy: &ConvertPolicy,
+    ) -> Result<WindowsPathBuf, ConvertError> {
+        windows::unix_to_windows(self, policy)
+    }
+
+    // Lexically normalizes the path: CurDir components and repeated
+    // separators are removed, and each ParentDir cancels out the Normal
+    // component before it. A ParentDir that would climb above the root is
+    // dropped, but leading ParentDirs of a relative path are kept. An empty
+    // relative result becomes .
+    pub fn normalize(&self) -> Result<UnixPathBuf, ParseError> {
+        let normalized = Normalized::new(&self.path)?;
+        Ok(normalized.to_pathbuf(self.as_bytes().len()))
+    }
+
+    // The path that leads from base to this path, eg ../../x/y. Both paths
+    // are normalized first, and both must be either absolute or relative.
+    pub fn relative_to<P: AsRef<OsStr> + ?Sized>(
+        &self,
+        base: &P,
+    ) -> Result<UnixPathBuf, RelativePathError> {
+        let path = Normalized::new(&self.path)?;
+        let base = Normalized::new(SystemStr::new(base))?;
+        if path.has_root != base.has_root {
+            return Err(RelativePathError::RootMismatch);
+        }
+
+        let common = path
+            .comps
+            .iter()
+            .zip(&base.comps)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let base_rest = &base.comps[common..];
+        if base_rest.contains(&Component::ParentDir) {
+            return Err(RelativePathError::UnknownBase);
+        }
+
+        let mut comps = vec![Component::ParentDir; base_rest.len()];
+        comps.extend_from_slice(&path.comps[common..]);
+        let relative = Normalized {
+            has_root: false,
+            comps,
+        };
+        Ok(relative.to_pathbuf(self.as_bytes().len()))
+    }
+
+    pub fn clean(&self) -> Result<UnixPathBuf, ParseError> {
+        self.normalize()
+    }
+
+    pub fn join<P: AsRef<OsStr> + ?Sized>(&self, path: &P) -> UnixPathBuf {
+        let mut pathbuf = UnixPathBuf::from(&self.path);
+        pathbuf.push(path);
+        pathbuf
+    }
+}
+
 impl Deref for UnixPath {
     type Target = SystemStr;
 
@@ -86,7 +381,7 @@ impl Deref for UnixPath {
 
 impl AsSystemStr for &UnixPath {
     fn as_sys_str(&self) -> &SystemStr {
-        &*self
+        self
     }
 }
 
@@ -103,6 +398,26 @@ impl<'path> Iterator for PathParts<'path, Iter<'path>> {
     }
 }
 
+// --------------------
+// Ancestors
+// --------------------
+
+// Yields a path followed by each of its parents
+#[derive(Debug, Clone, Copy)]
+pub struct Ancestors<'path> {
+    next: Option<&'path UnixPath>,
+}
+
+impl<'path> Iterator for Ancestors<'path> {
+    type Item = &'path UnixPath;
+
+    fn next(&mut self) -> Option<&'path UnixPath> {
+        let next = self.next?;
+        self.next = next.parent();
+        Some(next)
+    }
+}
+
 // --------------------
 // UnixPathBuf
 // --------------------
@@ -116,6 +431,191 @@ impl UnixPathBuf {
     pub fn new() -> UnixPathBuf {
         Default::default()
     }
+
+    pub fn with_capacity(capacity: usize) -> UnixPathBuf {
+        UnixPathBuf {
+            pathbuf: SystemString::with_capacity(capacity),
+        }
+    }
+
+    // An absolute path replaces the current path, anything else is appended
+    // to it after a separator
+    pub fn push<P: AsRef<OsStr> + ?Sized>(&mut self, path: &P) {
+        let path = SystemStr::new(path);
+        if root_len(path.as_bytes()) > 0 {
+            self.pathbuf = SystemString::from(path);
+            return;
+        }
+
+        let needs_sep = match self.pathbuf.as_bytes().last() {
+            Some(&last) => Separator != last,
+            None => false,
+        };
+        if needs_sep {
+            self.pathbuf.push("/");
+        }
+        self.pathbuf.push(path);
+    }
+
+    // Removes the last component, returning false if there is only a root or
+    // nothing at all left to remove
+    pub fn pop(&mut self) -> bool {
+        match parent_len(self.pathbuf.as_bytes()) {
+            Some(len) => {
+                self.pathbuf.truncate(len);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn as_path(&self) -> &UnixPath {
+        UnixPath::new(&self.pathbuf)
+    }
+
+    pub fn parent(&self) -> Option<&UnixPath> {
+        self.as_path().parent()
+    }
+
+    pub fn ancestors(&self) -> Ancestors<'_> {
+        self.as_path().ancestors()
+    }
+
+    pub fn tokens(&self) -> Tokens<'_> {
+        self.as_path().tokens()
+    }
+
+    pub fn validate_all(&self) -> Vec<ParseError> {
+        self.as_path().validate_all()
+    }
+
+    pub fn as_windows(&self) -> SeparatorDisplay<'_> {
+        self.as_path().as_windows()
+    }
+
+    pub fn to_windows(
+        &self,
+        policy: &ConvertPolicy,
+    ) -> Result<WindowsPathBuf, ConvertError> {
+        self.as_path().to_windows(policy)
+    }
+
+    pub fn normalize(&self) -> Result<UnixPathBuf, ParseError> {
+        self.as_path().normalize()
+    }
+
+    pub fn clean(&self) -> Result<UnixPathBuf, ParseError> {
+        self.normalize()
+    }
+
+    pub fn relative_to<P: AsRef<OsStr> + ?Sized>(
+        &self,
+        base: &P,
+    ) -> Result<UnixPathBuf, RelativePathError> {
+        self.as_path().relative_to(base)
+    }
+
+    pub fn join<P: AsRef<OsStr> + ?Sized>(&self, path: &P) -> UnixPathBuf {
+        let mut pathbuf = self.clone();
+        pathbuf.push(path);
+        pathbuf
+    }
+
+    // Replaces the file name, or pushes name if there is no file name (eg
+    // the path is / or ends in ..). Any trailing separators or curdir
+    // components after the old file name are dropped.
+    pub fn set_file_name<S: AsRef<OsStr> + ?Sized>(
+        &mut self,
+        name: &S,
+    ) -> Result<(), ParseError> {
+        let name = SystemStr::new(name);
+        validate_file_name(name)?;
+
+        match self.file_name_start() {
+            Some(start) => {
+                self.pathbuf.truncate(start);
+                self.pathbuf.push(name);
+            }
+            None => self.push(name),
+        }
+        Ok(())
+    }
+
+    // Replaces the extension of the file name, or removes it if ext is
+    // empty. Returns false without changing anything if there is no file
+    // name.
+    pub fn set_extension<S: AsRef<OsStr> + ?Sized>(
+        &mut self,
+        ext: &S,
+    ) -> Result<bool, ParseError> {
+        let mut name = match self.file_stem() {
+            Some(stem) => stem.to_os_string(),
+            None => return Ok(false),
+        };
+        let ext = ext.as_ref();
+        if !ext.is_empty() {
+            name.push(".");
+            name.push(ext);
+        }
+
+        self.set_file_name(&name)?;
+        Ok(true)
+    }
+
+    pub fn with_name<S: AsRef<OsStr> + ?Sized>(
+        &self,
+        name: &S,
+    ) -> Result<UnixPathBuf, ParseError> {
+        let mut pathbuf = self.clone();
+        pathbuf.set_file_name(name)?;
+        Ok(pathbuf)
+    }
+
+    // Replaces the file stem, keeping the current extension
+    pub fn with_stem<S: AsRef<OsStr> + ?Sized>(
+        &self,
+        stem: &S,
+    ) -> Result<UnixPathBuf, ParseError> {
+        let mut name = stem.as_ref().to_os_string();
+        if let Some(ext) = self.extension() {
+            name.push(".");
+            name.push(ext);
+        }
+        self.with_name(&name)
+    }
+
+    // Replaces the last suffix of the file name. The suffix must either be
+    // empty, which removes the current suffix, or start with a dot, and
+    // there must be a file name to add it to.
+    pub fn with_suffix<S: AsRef<OsStr> + ?Sized>(
+        &self,
+        suffix: &S,
+    ) -> Result<UnixPathBuf, SuffixError> {
+        let suffix = SystemStr::new(suffix).as_bytes();
+        if !suffix.is_empty() && (suffix[0] != b'.' || suffix.len() == 1) {
+            let msg = String::from("suffix must be a dot followed by a name");
+            return Err(SuffixError::Parse(file_name_error(
+                UnixErrorKind::InvalidCharacter,
+                suffix,
+                0,
+                suffix.len(),
+                msg,
+            )));
+        }
+
+        let mut name = match self.file_stem() {
+            Some(stem) => stem.to_os_string(),
+            None => return Err(SuffixError::NoFileName),
+        };
+        name.push(as_osstr(suffix));
+        Ok(self.with_name(&name)?)
+    }
+
+    // Byte offset of the file name within the path
+    fn file_name_start(&self) -> Option<usize> {
+        let name = SystemStr::new(self.file_name()?);
+        Some(offset_in(self.pathbuf.as_bytes(), name.as_bytes()))
+    }
 }
 
 impl Deref for UnixPathBuf {
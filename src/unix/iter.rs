@@ -29,12 +29,15 @@ mod iter_imports {
 
 // Stdlib imports
 use std::ffi::OsStr;
+use std::ops::Range;
 
 // Third-party imports
 
 // Local imports
-use crate::common::string::as_str;
-use crate::path::SystemStr;
+use crate::common::error::ParseError;
+use crate::common::string::{as_osstr, as_str};
+use crate::path::{PathIterator, SystemStr};
+use crate::unix::path_type::Separator;
 
 // ===========================================================================
 // Re-exports
@@ -46,7 +49,7 @@ pub use self::iter_imports::*;
 // Component
 // ===========================================================================
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum Component<'path> {
     RootDir,
     CurDir,
@@ -90,6 +93,150 @@ impl<'path> AsRef<SystemStr> for Component<'path> {
     }
 }
 
+// ===========================================================================
+// Spans
+// ===========================================================================
+
+pub type SpannedComponent<'path> =
+    Result<(Range<usize>, Component<'path>), ParseError>;
+
+// Yields each component along with the range of bytes of the path that it
+// was parsed from. An error already records where it was found.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Spans<'path> {
+    iter: Iter<'path>,
+}
+
+impl<'path> Iter<'path> {
+    pub fn spans(self) -> Spans<'path> {
+        Spans { iter: self }
+    }
+}
+
+impl<'path> Iterator for Spans<'path> {
+    type Item = SpannedComponent<'path>;
+
+    fn next(&mut self) -> Option<SpannedComponent<'path>> {
+        let comp = self.iter.next()?;
+        Some(comp.map(|c| (self.iter.span(), c)))
+    }
+}
+
+impl<'path> DoubleEndedIterator for Spans<'path> {
+    fn next_back(&mut self) -> Option<SpannedComponent<'path>> {
+        let comp = self.iter.next_back()?;
+        Some(comp.map(|c| (self.iter.span(), c)))
+    }
+}
+
+// ===========================================================================
+// Tokens
+// ===========================================================================
+
+// A piece of a path as it was written. Unlike Component, separators and
+// empty segments are kept, so concatenating every Token of a path gives the
+// path back exactly.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Token<'path> {
+    RootDir,
+    Separator,
+
+    // The segment between two consecutive separators
+    Empty,
+    Normal(&'path OsStr),
+}
+
+impl<'path> Token<'path> {
+    pub fn as_os_str(&self) -> &'path OsStr {
+        match self {
+            Token::RootDir | Token::Separator => OsStr::new("/"),
+            Token::Empty => OsStr::new(""),
+            Token::Normal(part) => part,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum TokenState {
+    Start,
+    Segment,
+    Separator,
+    Finish,
+}
+
+// Splits a path into Tokens. The root is found the same way that Iter finds
+// it, but no segment is validated, so use Iter to check the path.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Tokens<'path> {
+    path: &'path [u8],
+    state: TokenState,
+    cur: usize,
+    comp_start: usize,
+}
+
+impl<'path> PathIterator<'path> for Tokens<'path> {
+    fn new(path: &'path SystemStr) -> Tokens<'path> {
+        Tokens {
+            path: path.as_ref(),
+            state: TokenState::Start,
+            cur: 0,
+            comp_start: Iter::new(path).components_start(),
+        }
+    }
+}
+
+impl<'path> Tokens<'path> {
+    fn next_segment(&mut self) -> Option<Token<'path>> {
+        let (path, start) = (self.path, self.cur);
+        if start >= path.len() {
+            self.state = TokenState::Finish;
+            return None;
+        }
+
+        let end = match path[start..].iter().position(|&b| Separator == b) {
+            Some(i) => {
+                self.state = TokenState::Separator;
+                start + i
+            }
+            None => {
+                self.state = TokenState::Finish;
+                path.len()
+            }
+        };
+        self.cur = end;
+
+        if start == end {
+            Some(Token::Empty)
+        } else {
+            Some(Token::Normal(as_osstr(&path[start..end])))
+        }
+    }
+}
+
+impl<'path> Iterator for Tokens<'path> {
+    type Item = Token<'path>;
+
+    fn next(&mut self) -> Option<Token<'path>> {
+        match self.state {
+            TokenState::Start => {
+                self.state = TokenState::Segment;
+                if self.comp_start > 0 {
+                    self.cur = self.comp_start;
+                    return Some(Token::RootDir);
+                }
+                self.next_segment()
+            }
+            TokenState::Segment => self.next_segment(),
+            TokenState::Separator => {
+                self.cur += 1;
+                self.state = TokenState::Segment;
+                Some(Token::Separator)
+            }
+            TokenState::Finish => None,
+        }
+    }
+}
+
 // ===========================================================================
 //
 // ===========================================================================
@@ -19,6 +19,7 @@
 
 // Stdlib imports
 use std::ffi::OsString;
+use std::ops::Range;
 
 // Third-party imports
 
@@ -28,8 +29,9 @@ use crate::common::error::ParseError;
 use crate::common::string::as_osstr;
 use crate::path::{PathIterator, SystemStr};
 use crate::unix::{
+    components_end,
     path_type::{Null, Separator},
-    PathParseState, UnixErrorKind,
+    BackParseState, PathParseState, UnixErrorKind,
 };
 
 // ===========================================================================
@@ -54,33 +56,67 @@ fn as_os_string(path: &[u8]) -> OsString {
 pub struct Iter<'path> {
     path: &'path [u8],
     parse_state: PathParseState,
+    back_state: BackParseState,
     cur: usize,
+
+    // The components after the root lie between comp_start and end, and
+    // next_back() moves end back towards cur as it parses them
+    comp_start: usize,
+    end: usize,
+
+    // Bytes of path that the last component returned from either end was
+    // parsed from
+    span: Range<usize>,
+
+    // Whether to carry on with the next component after an invalid one
+    recover: bool,
 }
 
 impl<'path> PathIterator<'path> for Iter<'path> {
-    fn new(path: &SystemStr) -> Iter {
+    fn new(path: &SystemStr) -> Iter<'_> {
+        let path: &[u8] = path.as_ref();
+        let comp_start = match path.first() {
+            Some(&first) if Separator == first => 1,
+            _ => 0,
+        };
+
         Iter {
-            path: path.as_ref(),
+            path,
             parse_state: PathParseState::Start,
+            back_state: BackParseState::PathComponent,
             cur: 0,
+            comp_start,
+            end: components_end(path, comp_start),
+            span: 0..0,
+            recover: false,
         }
     }
 }
 
 impl<'path> Iter<'path> {
-    // unix_iter_body!(PathComponent<'path>, Component<'path>);
-    fn parse_root(&mut self) -> Option<PathComponent<'path>> {
-        // This case will only happen if the input path is empty
-        if self.cur == self.path.len() {
-            self.parse_state = PathParseState::PathComponent;
-            return Some(Ok(Component::CurDir));
+    // Makes the iterator return an error for each invalid component and then
+    // continue with the next one, instead of stopping at the first error
+    pub fn recovering(mut self) -> Iter<'path> {
+        self.recover = true;
+        self
+    }
+
+    // Stops the iteration at an invalid component, unless the iterator is
+    // recovering from errors
+    fn stop_at_error(&mut self) {
+        if !self.recover {
+            self.parse_state = PathParseState::Finish;
         }
+    }
 
+    // unix_iter_body!(PathComponent<'path>, Component<'path>);
+    fn parse_root(&mut self) -> Option<PathComponent<'path>> {
         self.parse_state = PathParseState::Root;
 
         // Check for root
-        if Separator == self.path[self.cur] {
-            self.cur += 1;
+        if self.comp_start > 0 {
+            self.cur = self.comp_start;
+            self.span = 0..self.comp_start;
             let ret = Component::RootDir;
             return Some(Ok(ret));
         }
@@ -89,49 +125,82 @@ impl<'path> Iter<'path> {
     }
 
     fn parse_component(&mut self) -> Option<PathComponent<'path>> {
-        let end = self.path.len();
         let cur = self.cur;
-
-        if cur == end {
+        if cur >= self.end {
             self.parse_state = PathParseState::Finish;
             return None;
         }
 
-        let mut ret = None;
-        let mut has_invalid_char = false;
-        for i in cur..end {
-            let cur_char = self.path[i];
-            if Null == cur_char {
-                // The null character is not allowed in unix filenames
-                has_invalid_char = true;
-            } else if Separator == cur_char {
-                let part_len = i - cur;
-                let comp = if part_len == 0 {
-                    Ok(Component::CurDir)
-                } else {
-                    self.build_comp(cur, i, has_invalid_char)
-                };
-                ret = Some(comp);
-                self.cur = i + 1;
-                break;
-            }
-        }
-
         match self.parse_state {
             PathParseState::Finish | PathParseState::PathComponent => {}
             _ => self.parse_state = PathParseState::PathComponent,
         }
 
-        match ret {
-            Some(_) => ret,
+        let end = self.end.min(self.path.len());
+        match self.path[cur..end].iter().position(|&b| Separator == b) {
+            Some(i) => {
+                self.cur = cur + i + 1;
+                Some(self.parse_part(cur, cur + i))
+            }
+
+            // This is the last component
             None => {
-                let comp = self.build_comp(cur, end, has_invalid_char);
                 self.cur = end;
-                Some(comp)
+                self.end = end;
+                Some(self.parse_part(cur, end))
             }
         }
     }
 
+    fn parse_component_back(&mut self) -> Option<PathComponent<'path>> {
+        let start = self.cur.max(self.comp_start);
+        if start >= self.end {
+            self.back_state = BackParseState::Root;
+            return self.parse_root_back();
+        }
+
+        // The last component ends just before its separator
+        let end = self.end - 1;
+        match self.path[start..end].iter().rposition(|&b| Separator == b) {
+            Some(i) => {
+                self.end = start + i + 1;
+                Some(self.parse_part(self.end, end))
+            }
+
+            // This is the first component that is left
+            None => {
+                self.end = start;
+                Some(self.parse_part(start, end))
+            }
+        }
+    }
+
+    fn parse_root_back(&mut self) -> Option<PathComponent<'path>> {
+        // Nothing else is left once the root is reached
+        let root_left =
+            self.comp_start > 0 && self.parse_state == PathParseState::Start;
+        self.parse_state = PathParseState::Finish;
+
+        if root_left {
+            self.span = 0..self.comp_start;
+            Some(Ok(Component::RootDir))
+        } else {
+            None
+        }
+    }
+
+    // Parses the component between start and end, which both ends of the
+    // iterator use so that they report the same errors
+    fn parse_part(&mut self, start: usize, end: usize) -> PathComponent<'path> {
+        self.span = start..end;
+        if start == end {
+            return Ok(Component::CurDir);
+        }
+
+        let has_invalid_char = self.path[start..end].iter().any(|&b| Null == b);
+        self.build_comp(start, end, has_invalid_char)
+    }
+
     fn build_comp(
         &mut self,
         start: usize,
@@ -150,14 +219,13 @@ impl<'path> Iter<'path> {
         start: usize,
         end: usize,
     ) -> Result<Component<'path>, ParseError> {
-        // Return None for every call to next() after this
-        self.parse_state = PathParseState::Finish;
+        self.stop_at_error();
 
         let msg = String::from("path component contains an invalid character");
         let err = ParseError::new(
             UnixErrorKind::InvalidCharacter.into(),
             as_os_string(&self.path[start..end]),
-            as_os_string(&self.path[..]),
+            as_os_string(self.path),
             start,
             end,
             msg,
@@ -166,6 +234,15 @@ impl<'path> Iter<'path> {
         Err(err)
     }
 
+    pub(crate) fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    // Where the components that follow the root begin
+    pub(crate) fn components_start(&self) -> usize {
+        self.comp_start
+    }
+
     #[cfg(test)]
     pub fn current_index(&self) -> usize {
         self.cur
@@ -186,6 +263,19 @@ impl<'path> Iterator for Iter<'path> {
     }
 }
 
+impl<'path> DoubleEndedIterator for Iter<'path> {
+    fn next_back(&mut self) -> Option<PathComponent<'path>> {
+        if self.parse_state == PathParseState::Finish {
+            return None;
+        }
+
+        match self.back_state {
+            BackParseState::PathComponent => self.parse_component_back(),
+            BackParseState::Root => self.parse_root_back(),
+        }
+    }
+}
+
 impl<'path> AsRef<SystemStr> for Iter<'path> {
     fn as_ref(&self) -> &SystemStr {
         SystemStr::from_bytes(&self.path[self.cur..])
@@ -8,16 +8,17 @@
 // ===========================================================================
 
 // Stdlib imports
+use std::ops::Range;
 
 // Third-party imports
-use combine::Parser;
+use combine::{easy::Errors, stream::PointerOffset, Parser};
 
 // Local imports
 use crate::path::{PathIterator, SystemStr};
 use crate::unix::{
-    iter::Component,
+    components_end,
     parser::{component, into_error, root},
-    PathParseState,
+    BackParseState, PathParseState,
 };
 
 // ===========================================================================
@@ -26,6 +27,15 @@ use crate::unix::{
 
 pub use crate::unix::parser::PathComponent;
 
+// ===========================================================================
+// Types
+// ===========================================================================
+
+type ComponentResult<'path> = Result<
+    ((PathComponent<'path>, usize), &'path [u8]),
+    Errors<u8, &'path [u8], PointerOffset>,
+>;
+
 // ===========================================================================
 // Iter
 // ===========================================================================
@@ -34,28 +44,60 @@ pub use crate::unix::parser::PathComponent;
 pub struct Iter<'path> {
     path: &'path [u8],
     parse_state: PathParseState,
+    back_state: BackParseState,
     cur: usize,
+
+    // The components after the root lie between comp_start and end, and
+    // next_back() moves end back towards cur as it parses them
+    comp_start: usize,
+    end: usize,
+
+    // Bytes of path that the last component returned from either end was
+    // parsed from
+    span: Range<usize>,
+
+    // Whether to carry on with the next component after an invalid one
+    recover: bool,
 }
 
 impl<'path> PathIterator<'path> for Iter<'path> {
-    fn new(path: &SystemStr) -> Iter {
+    fn new(path: &SystemStr) -> Iter<'_> {
         let p = path.as_ref();
+        let comp_start = match root().parse(p) {
+            Ok(((_, len), _)) => len,
+            Err(_) => 0,
+        };
+
         Iter {
             path: p,
             parse_state: PathParseState::Start,
+            back_state: BackParseState::PathComponent,
             cur: 0,
+            comp_start,
+            end: components_end(p, comp_start),
+            span: 0..0,
+            recover: false,
         }
     }
 }
 
 impl<'path> Iter<'path> {
-    fn parse_root(&mut self) -> Option<PathComponent<'path>> {
-        // This case will only happen if the input path is empty
-        if self.path.is_empty() {
-            self.parse_state = PathParseState::PathComponent;
-            return Some(Ok(Component::CurDir));
+    // Makes the iterator return an error for each invalid component and then
+    // continue with the next one, instead of stopping at the first error
+    pub fn recovering(mut self) -> Iter<'path> {
+        self.recover = true;
+        self
+    }
+
+    // Stops the iteration at an invalid component, unless the iterator is
+    // recovering from errors
+    fn stop_at_error(&mut self) {
+        if !self.recover {
+            self.parse_state = PathParseState::Finish;
         }
+    }
 
+    fn parse_root(&mut self) -> Option<PathComponent<'path>> {
         self.parse_state = PathParseState::Root;
 
         let result = root().parse(self.path);
@@ -63,14 +105,14 @@ impl<'path> Iter<'path> {
             Err(_) => self.parse_component(),
             Ok(((comp, len), _)) => {
                 self.cur = len;
+                self.span = 0..len;
                 Some(comp)
             }
         }
     }
 
     fn parse_component(&mut self) -> Option<PathComponent<'path>> {
-        let path_len = self.path.len();
-        if self.cur == path_len {
+        if self.cur >= self.end {
             self.parse_state = PathParseState::Finish;
             return None;
         }
@@ -80,26 +122,103 @@ impl<'path> Iter<'path> {
             _ => self.parse_state = PathParseState::PathComponent,
         }
 
-        let remaining = &self.path[self.cur..];
-        let result = component().easy_parse(remaining);
-        match result {
-            Err(err) => {
-                self.parse_state = PathParseState::Finish;
-                Some(Err(into_error(self.path, self.cur, err)))
+        let start = self.cur;
+        let ret = self.parse_part(start);
+        let end = self.end.min(self.path.len());
+        self.cur = match ret {
+            Ok(((_, len), _)) => start + len,
+
+            // Skip the rest of an invalid component in case the iterator is
+            // recovering from errors
+            Err(_) => {
+                match self.path[start..end].iter().position(|&b| b == b'/') {
+                    Some(i) => start + i,
+                    None => end,
+                }
             }
+        };
+        if self.cur < end {
+            // At this point, the very next byte is the separator, so it
+            // is safe to increment self.cur by 1 to make sure the next
+            // iteration does not include the separator
+            self.cur += 1;
+        } else {
+            // This is the last component
+            self.end = self.cur;
+        }
+        Some(self.component_result(start, ret))
+    }
+
+    fn parse_component_back(&mut self) -> Option<PathComponent<'path>> {
+        let start = self.cur.max(self.comp_start);
+        if start >= self.end {
+            self.back_state = BackParseState::Root;
+            return self.parse_root_back();
+        }
+
+        // The last component ends just before its separator
+        let end = self.end - 1;
+        let comp_start =
+            match self.path[start..end].iter().rposition(|&b| b == b'/') {
+                Some(i) => start + i + 1,
+
+                // This is the first component that is left
+                None => start,
+            };
+        self.end = comp_start;
+
+        let ret = self.parse_part(comp_start);
+        Some(self.component_result(comp_start, ret))
+    }
+
+    fn parse_root_back(&mut self) -> Option<PathComponent<'path>> {
+        // Nothing else is left once the root is reached
+        let root_left =
+            self.comp_start > 0 && self.parse_state == PathParseState::Start;
+        self.parse_state = PathParseState::Finish;
+
+        if root_left {
+            self.span = 0..self.comp_start;
+            root().parse(self.path).ok().map(|((comp, _), _)| comp)
+        } else {
+            None
+        }
+    }
+
+    // Parses the component that starts at start, which both ends of the
+    // iterator use so that they report the same errors
+    fn parse_part(&self, start: usize) -> ComponentResult<'path> {
+        component().easy_parse(&self.path[start..])
+    }
+
+    fn component_result(
+        &mut self,
+        start: usize,
+        result: ComponentResult<'path>,
+    ) -> PathComponent<'path> {
+        match result {
             Ok(((comp, len), _)) => {
-                self.cur += len;
-                if self.cur < path_len {
-                    // At this point, the very next byte is the separator, so it
-                    // is safe to increment self.cur by 1 to make sure the next
-                    // iteration does not include the separator
-                    self.cur += 1;
-                }
-                Some(comp)
+                self.span = start..start + len;
+                comp
+            }
+            Err(err) => {
+                self.stop_at_error();
+                Err(into_error(self.path, start, err))
             }
         }
     }
 
+    #[cfg(not(feature = "manual-iter"))]
+    pub(crate) fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    // Where the components that follow the root begin
+    #[cfg(not(feature = "manual-iter"))]
+    pub(crate) fn components_start(&self) -> usize {
+        self.comp_start
+    }
+
     #[allow(dead_code)]
     #[cfg(test)]
     pub fn current_index(&self) -> usize {
@@ -122,6 +241,19 @@ impl<'path> Iterator for Iter<'path> {
     }
 }
 
+impl<'path> DoubleEndedIterator for Iter<'path> {
+    fn next_back(&mut self) -> Option<PathComponent<'path>> {
+        if self.parse_state == PathParseState::Finish {
+            return None;
+        }
+
+        match self.back_state {
+            BackParseState::PathComponent => self.parse_component_back(),
+            BackParseState::Root => self.parse_root_back(),
+        }
+    }
+}
+
 impl<'path> AsRef<SystemStr> for Iter<'path> {
     fn as_ref(&self) -> &SystemStr {
         SystemStr::from_bytes(&self.path[self.cur..])
@@ -21,7 +21,7 @@
 
 // Third-party imports
 use combine::{
-    choice,
+    attempt, choice,
     easy::Errors,
     eof,
     error::ParseError,
@@ -166,7 +166,13 @@ where
                 (Ok(Component::Normal(as_osstr(comp))), comp.len())
             }
         });
-    let comp_option = (look_ahead(parentdir()), look_ahead(curdir()), comp);
+    // Names such as .bashrc start out matching curdir/parentdir, so those
+    // need to be able to backtrack
+    let comp_option = (
+        attempt(look_ahead(parentdir())),
+        attempt(look_ahead(curdir())),
+        comp,
+    );
     choice(comp_option)
 }
 
@@ -88,7 +88,7 @@ mod test {
 
         #[test]
         fn equal_to_sep() {
-            let s = vec![b'/'];
+            let s = [b'/'];
             assert_eq!(Separator, &s[..]);
             assert_eq!(&s[..], Separator);
 
@@ -104,7 +104,6 @@ mod test {
                 prop_assert_ne!(Separator, &arr[..]);
             }
         }
-
     }
 
     mod null {
@@ -119,7 +118,7 @@ mod test {
 
         #[test]
         fn equal_to_sep() {
-            let s = vec![b'\x00'];
+            let s = [b'\x00'];
             assert_eq!(Null, &s[..]);
             assert_eq!(&s[..], Null);
 
@@ -135,7 +134,6 @@ mod test {
                 prop_assert_ne!(Null, &arr[..]);
             }
         }
-
     }
 }
 
@@ -8,8 +8,15 @@
 // ===========================================================================
 
 // Private modules
+mod convert;
 pub mod iter;
+mod mount;
 mod path_type;
+mod prefix;
+mod private_use;
+mod short_name;
+mod stream;
+mod unc;
 
 #[cfg(feature = "manual-iter")]
 mod match_prefix;
@@ -31,21 +38,43 @@ use std::ops::Deref;
 use lazy_static::lazy_static;
 
 // Local imports
+use self::path_type::{FileExtension, Separator, ShortName, ValidLastChar};
+use self::short_name::is_short_alias;
+use crate::common::error::{
+    ConvertError, ParseError, RelativePathError, SuffixError,
+};
+use crate::common::string::{as_osstr, offset_in};
 use crate::path::{
-    AsSystemStr, Path, PathBuf, PathParts, PathPartsExt as _, SystemStr,
-    SystemString,
+    AsSystemStr, Path, PathBuf, PathItem, PathIterator, PathParts,
+    PathPartsExt as _, SeparatorDisplay, SystemSeq, SystemStr, SystemString,
 };
+use crate::unix::UnixPathBuf;
 
 // ===========================================================================
 // Re-exports
 // ===========================================================================
 
-pub use self::iter::{Component, Iter, PathComponent, Prefix, PrefixComponent};
+pub(crate) use self::convert::unix_to_windows;
+pub use self::convert::{ConvertPolicy, OnInvalid, RootMap};
+pub use self::iter::{
+    Component, Iter, PathComponent, Prefix, PrefixComponent, SpannedComponent,
+    Spans, Token, Tokens,
+};
+pub use self::mount::MountTable;
+pub use self::prefix::PrefixKind;
+pub use self::private_use::{decode_private_use, encode_private_use};
+pub use self::short_name::short_name_candidate;
+pub use self::stream::FileStream;
+pub use self::unc::{UncHost, UncShare};
 
 // ===========================================================================
 // Constants
 // ===========================================================================
 
+// The longest path that the Win32 API accepts without a verbatim prefix, in
+// UTF-16 code units and including the terminating null
+const MAX_PATH: usize = 260;
+
 lazy_static! {
     static ref SEPARATOR: HashSet<u8> = {
         let sep_chars = r#"\/"#;
@@ -64,17 +93,21 @@ lazy_static! {
         all_letters
     };
     static ref RESERVED_NAMES: HashSet<String> = {
-        let base = ["CON", "PRN", "AUX", "NUL"];
+        let base = ["CON", "PRN", "AUX", "NUL", "CONIN$", "CONOUT$"];
         let numbered_base = ["COM", "LPT"];
-        let mut reserved = HashSet::with_capacity(22);
+        let digits = ["¹", "²", "³"];
+        let mut reserved = HashSet::with_capacity(32);
         for b in base.iter() {
             reserved.insert(b.to_string());
         }
 
         for b in numbered_base.iter() {
-            for i in 1..=9 {
+            for i in 0..=9 {
                 reserved.insert(format!("{}{}", b, i));
             }
+            for d in digits.iter() {
+                reserved.insert(format!("{}{}", b, d));
+            }
         }
         reserved
     };
@@ -101,6 +134,450 @@ lazy_static! {
 pub enum WindowsErrorKind {
     InvalidCharacter,
     RestrictedName,
+
+    // The server of a path that starts with \\ is not a valid host name or
+    // address, eg \\bad host\share
+    InvalidHost,
+}
+
+// ===========================================================================
+// Types needed for Iter
+// ===========================================================================
+
+// What next_back() parses next, which is the reverse of the order that
+// next() parses a path in
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+enum BackParseState {
+    PathComponent,
+    Root,
+    Prefix,
+}
+
+// ===========================================================================
+// Helpers
+// ===========================================================================
+
+// End of the components that follow the prefix and root, for an Iter that
+// can parse them from either end. Every component is treated as being
+// followed by a separator, so a path that does not end in one gets a
+// virtual separator just past its last byte.
+fn components_end(path: &[u8], start: usize, verbatim: bool) -> usize {
+    match path.last() {
+        Some(_) if start == path.len() => start,
+        Some(&last) if is_component_separator(last, verbatim) => path.len(),
+        _ => path.len() + 1,
+    }
+}
+
+// Whether Windows maps a file name to a DOS device, eg NUL.tar.gz or COM1:.
+// Everything from the first dot is ignored, as are any spaces or colons
+// that come before it.
+fn is_reserved_name(name: &[u8]) -> bool {
+    let end = name.iter().position(|&b| b == b'.').unwrap_or(name.len());
+    let base = &name[..end];
+    let base_len = base
+        .iter()
+        .rposition(|&b| b != b' ' && b != b':')
+        .map_or(0, |i| i + 1);
+
+    match std::str::from_utf8(&base[..base_len]) {
+        Ok(base) => RESERVED_NAMES.contains(&base.to_ascii_uppercase()),
+        Err(_) => false,
+    }
+}
+
+// Whether byte separates two components. Windows does no normalization
+// after a verbatim prefix, so only \ is a separator there and / is an
+// ordinary character.
+fn is_component_separator(byte: u8, verbatim: bool) -> bool {
+    if verbatim {
+        byte == b'\\'
+    } else {
+        Separator == byte
+    }
+}
+
+// The prefix and root of a path as parsed by Iter. The root is always a
+// single separator, but for verbatim disk prefixes it overlaps with the last
+// byte of the prefix.
+struct PathRoot<'path> {
+    prefix: Option<Prefix<'path>>,
+    prefix_len: usize,
+    has_root: bool,
+    len: usize,
+}
+
+impl<'path> PathRoot<'path> {
+    fn new(path: &'path [u8]) -> PathRoot<'path> {
+        let mut root = PathRoot {
+            prefix: None,
+            prefix_len: 0,
+            has_root: false,
+            len: 0,
+        };
+
+        let mut iter = Iter::new(SystemStr::from_bytes(path));
+        while let Some(Ok(comp)) = iter.next() {
+            let rest: &SystemStr = iter.as_ref();
+            let consumed = path.len() - rest.as_bytes().len();
+            match comp {
+                Component::Prefix(p) => {
+                    root.prefix = Some(p.kind());
+                    root.prefix_len = consumed;
+                }
+                Component::RootDir(_) => root.has_root = true,
+                _ => break,
+            }
+            root.len = consumed;
+        }
+
+        root
+    }
+
+    fn is_verbatim(&self) -> bool {
+        match self.prefix {
+            Some(prefix) => prefix.is_verbatim(),
+            None => false,
+        }
+    }
+
+    // Length of the prefix without any root that it overlaps with
+    fn prefix_only_len(&self) -> usize {
+        if self.has_root {
+            self.len - 1
+        } else {
+            self.prefix_len
+        }
+    }
+}
+
+// Length of the path that remains once the last component is removed, or
+// None if there is no component after the prefix and root to remove
+fn parent_len(path: &[u8]) -> Option<usize> {
+    let root = PathRoot::new(path);
+    let verbatim = root.is_verbatim();
+    let is_sep = |&b: &u8| is_component_separator(b, verbatim);
+    let root_end = root.len;
+    let rest = &path[root_end..];
+
+    // Trailing separators are not part of the last component
+    let comp_end = rest.iter().rposition(|b| !is_sep(b))? + 1;
+    let comp_start = rest[..comp_end]
+        .iter()
+        .rposition(is_sep)
+        .map_or(0, |i| i + 1);
+    let parent_end = rest[..comp_start]
+        .iter()
+        .rposition(|b| !is_sep(b))
+        .map_or(0, |i| i + 1);
+
+    Some(root_end + parent_end)
+}
+
+// Checks that name would be parsed by Iter as a single normal component,
+// so that it can be used as the file name of a path
+fn validate_file_name(name: &SystemStr) -> Result<(), ParseError> {
+    let bytes = name.as_bytes();
+    if let Some(i) = bytes.iter().position(|&b| Separator == b) {
+        let msg = String::from("file name contains a path separator");
+        return Err(file_name_error(
+            WindowsErrorKind::InvalidCharacter,
+            bytes,
+            i,
+            i + 1,
+            msg,
+        ));
+    }
+
+    match Iter::new(name).next() {
+        Some(Ok(Component::Normal(_))) => Ok(()),
+        Some(Err(e)) => Err(e),
+
+        // Without separators, only a drive prefix (eg C: or C:foo) can be
+        // parsed as a prefix, and : is never valid in a file name
+        Some(Ok(Component::Prefix(_))) => {
+            let i = bytes.iter().position(|&b| b == b':').unwrap_or(0);
+            let msg = String::from("file name contains an invalid character");
+            Err(file_name_error(
+                WindowsErrorKind::InvalidCharacter,
+                bytes,
+                i,
+                i + 1,
+                msg,
+            ))
+        }
+        _ => {
+            let msg = String::from("file name uses a restricted name");
+            Err(file_name_error(
+                WindowsErrorKind::RestrictedName,
+                bytes,
+                0,
+                bytes.len(),
+                msg,
+            ))
+        }
+    }
+}
+
+fn file_name_error(
+    kind: WindowsErrorKind,
+    name: &[u8],
+    start: usize,
+    end: usize,
+    msg: String,
+) -> ParseError {
+    ParseError::new(
+        kind.into(),
+        as_osstr(&name[start..end]).to_os_string(),
+        as_osstr(name).to_os_string(),
+        start,
+        end,
+        msg,
+    )
+}
+
+// Whether a .. that follows the prefix and root is dropped, since it would
+// climb above the root. A drive relative path like C:..\x climbs above the
+// current directory of the drive instead, so its .. is kept.
+fn drops_leading_parent(
+    prefix: Option<PrefixComponent>,
+    has_root: bool,
+) -> bool {
+    match prefix {
+        _ if has_root => true,
+        Some(p) => !matches!(p.kind(), Prefix::Disk(_)),
+        None => false,
+    }
+}
+
+// The prefix, root and remaining components of a lexically normalized path.
+// The components of a verbatim path are kept as they are.
+struct Normalized<'path> {
+    prefix: Option<PrefixComponent<'path>>,
+    has_root: bool,
+    comps: Vec<Component<'path>>,
+}
+
+impl<'path> Normalized<'path> {
+    fn new(path: &'path SystemStr) -> Result<Normalized<'path>, ParseError> {
+        let mut prefix: Option<PrefixComponent> = None;
+        let mut has_root = false;
+        let mut comps: Vec<Component> = Vec::new();
+        for comp in Iter::new(path) {
+            let comp = comp?;
+            let verbatim = match prefix {
+                Some(p) => p.kind().is_verbatim(),
+                None => false,
+            };
+            match comp {
+                Component::Prefix(p) => prefix = Some(p),
+                Component::RootDir(_) => has_root = true,
+                _ if verbatim => comps.push(comp),
+                Component::CurDir => {}
+                Component::ParentDir => match comps.last() {
+                    Some(Component::Normal(_)) => {
+                        comps.pop();
+                    }
+                    _ if drops_leading_parent(prefix, has_root) => {}
+                    _ => comps.push(Component::ParentDir),
+                },
+                Component::Normal(_) => comps.push(comp),
+            }
+        }
+
+        Ok(Normalized {
+            prefix,
+            has_root,
+            comps,
+        })
+    }
+
+    fn is_verbatim(&self) -> bool {
+        match self.prefix {
+            Some(p) => p.kind().is_verbatim(),
+            None => false,
+        }
+    }
+
+    fn to_pathbuf(&self, capacity: usize) -> WindowsPathBuf {
+        let mut pathbuf = WindowsPathBuf::with_capacity(capacity);
+        if let Some(p) = self.prefix {
+            pathbuf.pathbuf.push(p.as_os_str());
+        }
+        self.push_rest(&mut pathbuf);
+        if pathbuf.as_bytes().is_empty() {
+            pathbuf.pathbuf.push(".");
+        }
+        pathbuf
+    }
+
+    // The path with its disk or UNC prefix written in verbatim form, or None
+    // if the path is not absolute
+    fn to_verbatim_pathbuf(&self, capacity: usize) -> Option<WindowsPathBuf> {
+        let mut pathbuf = WindowsPathBuf::with_capacity(capacity);
+        match self.prefix?.kind() {
+            Prefix::Disk(disk) if self.has_root => {
+                pathbuf.pathbuf.push(r"\\?\");
+                pathbuf.pathbuf.push(SystemStr::from_bytes(&[disk, b':']));
+            }
+            Prefix::UNC(server, share) => {
+                pathbuf.pathbuf.push(r"\\?\UNC\");
+                pathbuf.pathbuf.push(server);
+                pathbuf.pathbuf.push(r"\");
+                pathbuf.pathbuf.push(share);
+            }
+            _ => return None,
+        }
+        self.push_rest(&mut pathbuf);
+        Some(pathbuf)
+    }
+
+    // Pushes the root and components that follow the prefix
+    fn push_rest(&self, pathbuf: &mut WindowsPathBuf) {
+        if self.has_root {
+            pathbuf.pathbuf.push(r"\");
+        }
+        for (i, comp) in self.comps.iter().enumerate() {
+            if i > 0 {
+                pathbuf.pathbuf.push(r"\");
+            }
+            pathbuf.pathbuf.push(comp.as_os_str());
+        }
+    }
+}
+
+// Length of path in UTF-16 code units, which is how the Win32 API measures
+// it
+fn utf16_len(path: &OsStr) -> usize {
+    path.to_string_lossy().encode_utf16().count()
+}
+
+// The components of a path that simplify_verbatim() compares. A verbatim
+// disk prefix always has a root, which Iter does not return on its own
+// when nothing follows it, so roots are left out.
+fn verbatim_comps(path: &WindowsPath) -> Vec<PathComponent<'_>> {
+    Iter::new(path)
+        .filter(|c| !matches!(c, Ok(Component::RootDir(_))))
+        .collect()
+}
+
+// Whether a component of a path simplified from a verbatim one is the same
+// as the verbatim component
+fn same_as_verbatim(simple: &PathComponent, verbatim: &PathComponent) -> bool {
+    match (simple, verbatim) {
+        (Ok(Component::Prefix(simple)), Ok(Component::Prefix(verbatim))) => {
+            match (simple.kind(), verbatim.kind()) {
+                (Prefix::Disk(a), Prefix::VerbatimDisk(b)) => a == b,
+                (
+                    Prefix::UNC(server, share),
+                    Prefix::VerbatimUNC(vserver, vshare),
+                ) => server == vserver && share == vshare,
+                _ => false,
+            }
+        }
+        (Ok(simple), Ok(verbatim)) => simple == verbatim,
+        _ => false,
+    }
+}
+
+// Whether two components could name the same thing, either because they
+// only differ in case or because one is a short name for the other
+fn same_or_short_alias(a: &Component, b: &Component) -> bool {
+    match (a, b) {
+        (Component::Prefix(a), Component::Prefix(b)) => {
+            prefix_eq(a.kind(), b.kind())
+        }
+        (Component::RootDir(_), Component::RootDir(_)) => true,
+        (Component::Normal(a), Component::Normal(b)) => {
+            let (a, b) =
+                (SystemStr::new(a).as_bytes(), SystemStr::new(b).as_bytes());
+            a.eq_ignore_ascii_case(b)
+                || is_short_alias(a, b)
+                || is_short_alias(b, a)
+        }
+        _ => a == b,
+    }
+}
+
+// Whether two components are the same, with names compared
+// case-insensitively since Windows does not tell them apart
+fn same_name(a: &Component, b: &Component) -> bool {
+    match (a, b) {
+        (Component::Normal(a), Component::Normal(b)) => {
+            let (a, b) = (SystemStr::new(a), SystemStr::new(b));
+            a.as_bytes().eq_ignore_ascii_case(b.as_bytes())
+        }
+        _ => a == b,
+    }
+}
+
+fn prefix_eq(a: Prefix, b: Prefix) -> bool {
+    let eq = |a: &OsStr, b: &OsStr| {
+        let (a, b) = (SystemStr::new(a), SystemStr::new(b));
+        a.as_bytes().eq_ignore_ascii_case(b.as_bytes())
+    };
+
+    match (a, b) {
+        (Prefix::Verbatim(a), Prefix::Verbatim(b))
+        | (Prefix::DeviceNS(a), Prefix::DeviceNS(b)) => eq(a, b),
+        (Prefix::VerbatimUNC(a1, a2), Prefix::VerbatimUNC(b1, b2))
+        | (Prefix::UNC(a1, a2), Prefix::UNC(b1, b2)) => {
+            eq(a1, b1) && eq(a2, b2)
+        }
+        (Prefix::VerbatimDisk(a), Prefix::VerbatimDisk(b))
+        | (Prefix::Disk(a), Prefix::Disk(b)) => a.eq_ignore_ascii_case(&b),
+        _ => false,
+    }
+}
+
+impl<'path> PathItem<'path> for PathComponent<'path> {
+    fn is_curdir(&self) -> bool {
+        matches!(self, Ok(Component::CurDir))
+    }
+
+    fn normal(&self) -> Option<&'path OsStr> {
+        match self {
+            Ok(Component::Normal(name)) => Some(name),
+            _ => None,
+        }
+    }
+
+    fn is_root(&self) -> bool {
+        matches!(self, Ok(Component::RootDir(_)))
+    }
+
+    // Prefixes are compared case-insensitively, and roots are equal no
+    // matter which separator they use
+    fn same_component(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Ok(Component::Prefix(a)), Ok(Component::Prefix(b))) => {
+                prefix_eq(a.kind(), b.kind())
+            }
+            (Ok(Component::RootDir(_)), Ok(Component::RootDir(_))) => true,
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    // A name ending in an invalid last character (eg a trailing dot) has no
+    // suffix, otherwise the suffix must be a valid FileExtension that follows
+    // a non-empty stem
+    fn suffix_start(name: &[u8]) -> Option<usize> {
+        if name != ValidLastChar {
+            return None;
+        }
+
+        let start = name.iter().rposition(|&b| b == b'.')?;
+        if start > 0 && &name[start..] == FileExtension {
+            Some(start)
+        } else {
+            None
+        }
+    }
+
+    fn is_separator(byte: u8) -> bool {
+        Separator == byte
+    }
 }
 
 // ===========================================================================
@@ -128,6 +605,291 @@ impl WindowsPath {
     }
 }
 
+impl WindowsPath {
+    // The path without its last component, or None if there is no component
+    // after the prefix and root to remove
+    pub fn parent(&self) -> Option<&WindowsPath> {
+        let path = self.as_bytes();
+        let len = parent_len(path)?;
+        Some(WindowsPath::new(SystemStr::from_bytes(&path[..len])))
+    }
+
+    pub fn ancestors(&self) -> Ancestors<'_> {
+        Ancestors { next: Some(self) }
+    }
+
+    // The path split up as it was written, separators included
+    pub fn tokens(&self) -> Tokens<'_> {
+        Tokens::new(self)
+    }
+
+    // Every invalid component of the path, in order. Each error records the
+    // range of the path that it covers.
+    pub fn validate_all(&self) -> Vec<ParseError> {
+        Iter::new(self)
+            .recovering()
+            .filter_map(Result::err)
+            .collect()
+    }
+
+    // Whether a component of the path is a name that Windows reserves for a
+    // DOS device, so that the path cannot be used to open a file. Verbatim
+    // paths are passed to the filesystem as is and are never reserved.
+    pub fn is_reserved(&self) -> bool {
+        let path = self.as_bytes();
+        let root = PathRoot::new(path);
+        if root.is_verbatim() {
+            return false;
+        }
+        path[root.len..]
+            .split(|&b| Separator == b)
+            .any(is_reserved_name)
+    }
+
+    // The same path on a local drive, if it reaches the drive through the
+    // administrative share of this machine, eg \\localhost\C$\x becomes C:\x
+    // and \\?\UNC\127.0.0.1\C$\x becomes \\?\C:\x
+    pub fn to_disk_path(&self) -> Option<WindowsPathBuf> {
+        let prefix = match Iter::new(self).next() {
+            Some(Ok(Component::Prefix(prefix))) => prefix,
+            _ => return None,
+        };
+        let disk = match (prefix.unc_host()?, prefix.unc_share()?) {
+            (host, UncShare::Disk(disk)) if host.is_local() => disk,
+            _ => return None,
+        };
+
+        let rest = &self.as_bytes()[prefix.as_os_str().len()..];
+        let mut pathbuf = WindowsPathBuf::with_capacity(rest.len() + 7);
+        if prefix.kind().is_verbatim() {
+            pathbuf.pathbuf.push(r"\\?\");
+        }
+        pathbuf.pathbuf.push(SystemStr::from_bytes(&[disk, b':']));
+
+        // The share itself is the root of the drive
+        if rest.is_empty() {
+            pathbuf.pathbuf.push(r"\");
+        }
+        pathbuf.pathbuf.push(SystemStr::from_bytes(rest));
+        Some(pathbuf)
+    }
+
+    // The path without its \\?\ prefix, eg \\?\C:\dir becomes C:\dir and
+    // \\?\UNC\server\share\x becomes \\server\share\x. The path is returned
+    // unchanged unless the simpler path has the same components once Win32
+    // normalization is applied to it, and is short enough to not need the
+    // prefix.
+    pub fn simplify_verbatim(&self) -> WindowsPathBuf {
+        let path = self.as_bytes();
+        let comps = verbatim_comps(self);
+        let prefix = match comps.first() {
+            Some(Ok(Component::Prefix(prefix))) => prefix,
+            _ => return WindowsPathBuf::from(&self.path),
+        };
+
+        // Only \\?\C: and \\?\UNC have an ordinary form, which drops the
+        // \\?\ and UNC parts of the prefix
+        let simple = match prefix.classify() {
+            PrefixKind::VerbatimDisk(_) => Vec::from(&path[4..]),
+            PrefixKind::VerbatimUNC(..) => {
+                let mut simple = vec![b'\\'];
+                simple.extend_from_slice(&path[7..]);
+                simple
+            }
+            _ => return WindowsPathBuf::from(&self.path),
+        };
+
+        let simple = WindowsPath::new(as_osstr(&simple));
+        let too_long = utf16_len(simple.as_os_str()) >= MAX_PATH;
+        let simple_comps = verbatim_comps(simple);
+        let same = simple_comps.len() == comps.len()
+            && simple_comps
+                .iter()
+                .zip(comps.iter())
+                .all(|(s, v)| same_as_verbatim(s, v));
+
+        if same && !too_long {
+            WindowsPathBuf::from(simple.as_os_str())
+        } else {
+            WindowsPathBuf::from(&self.path)
+        }
+    }
+
+    // The normalized path, with a verbatim prefix if it is too long for the
+    // Win32 API without one, eg C:\dir\..\x becomes \\?\C:\x and
+    // \\server\share\x becomes \\?\UNC\server\share\x. Paths that are short
+    // enough, not absolute, or already verbatim are only normalized.
+    pub fn to_verbatim(&self) -> Result<WindowsPathBuf, ParseError> {
+        self.to_verbatim_with_limit(MAX_PATH)
+    }
+
+    // Like to_verbatim(), but the path gets a verbatim prefix once it is
+    // limit UTF-16 code units long. As with MAX_PATH, the limit counts the
+    // terminating null, so a limit of 0 always adds the prefix.
+    pub fn to_verbatim_with_limit(
+        &self,
+        limit: usize,
+    ) -> Result<WindowsPathBuf, ParseError> {
+        let normalized = Normalized::new(&self.path)?;
+        if normalized.is_verbatim() {
+            return Ok(WindowsPathBuf::from(&self.path));
+        }
+
+        let capacity = self.as_bytes().len();
+        let pathbuf = normalized.to_pathbuf(capacity);
+        if utf16_len(pathbuf.as_os_str()) < limit {
+            return Ok(pathbuf);
+        }
+        Ok(normalized
+            .to_verbatim_pathbuf(capacity + 8)
+            .unwrap_or(pathbuf))
+    }
+
+    // Whether a component of the path looks like an 8.3 short name that was
+    // generated for a longer name, eg PROGRA~1 or MYDOCU~2.TXT
+    pub fn has_short_names(&self) -> bool {
+        Iter::new(self).any(|comp| match comp {
+            Ok(Component::Normal(name)) => {
+                SystemStr::new(name).as_bytes() == ShortName
+            }
+            _ => false,
+        })
+    }
+
+    // Whether this path and other could name the same file once 8.3 short
+    // names are taken into account, eg C:\PROGRA~1\App and
+    // C:\Program Files\app. Components are compared case insensitively, and
+    // a short name matches any long name that NTFS could have generated it
+    // for. This is decided lexically, so a true result is only a
+    // possibility.
+    pub fn may_alias<P: AsRef<OsStr> + ?Sized>(&self, other: &P) -> bool {
+        let a: Vec<PathComponent> = Iter::new(self).collect();
+        let b: Vec<PathComponent> =
+            Iter::new(WindowsPath::new(other)).collect();
+        a.len() == b.len()
+            && a.iter().zip(b.iter()).all(|(a, b)| match (a, b) {
+                (Ok(a), Ok(b)) => same_or_short_alias(a, b),
+                _ => false,
+            })
+    }
+
+    // The file name split into its base name and the alternate data stream
+    // that it names, if any. Unlike file_name(), the path is parsed with
+    // stream names allowed in its last component.
+    pub fn file_stream(&self) -> Option<FileStream<'_>> {
+        // Parsed from the front so that the components before the file
+        // name are checked too, which next_back() would skip
+        #[allow(clippy::double_ended_iterator_last)]
+        let last = Iter::new(self)
+            .with_streams()
+            .filter(|c| !c.is_curdir())
+            .last()?;
+        last.normal().map(FileStream::new)
+    }
+
+    // Shows the path with / as its separator, eg C:\dir\file as
+    // C:/dir/file. A verbatim path is shown as it is, since / is an
+    // ordinary character after a verbatim prefix.
+    pub fn as_posix(&self) -> SeparatorDisplay<'_> {
+        self.display_with(b'/')
+    }
+
+    // Shows the path with \ as its only separator, eg C:/dir\file as
+    // C:\dir\file, which normalizes a path with mixed separators
+    pub fn as_windows(&self) -> SeparatorDisplay<'_> {
+        self.display_with(b'\\')
+    }
+
+    fn display_with(&self, separator: u8) -> SeparatorDisplay<'_> {
+        let path = self.as_bytes();
+        if PathRoot::new(path).is_verbatim() {
+            SeparatorDisplay::new(path, |_| false, separator)
+        } else {
+            SeparatorDisplay::new(path, |b| Separator == b, separator)
+        }
+    }
+
+    // The same path as a Unix one, eg C:\dir\file becomes /dir/file when
+    // policy maps / to C:\. A prefix must be the one that the policy maps
+    // the Unix root to.
+    pub fn to_unix(
+        &self,
+        policy: &ConvertPolicy,
+    ) -> Result<UnixPathBuf, ConvertError> {
+        convert::windows_to_unix(self, policy)
+    }
+
+    // Lexically normalizes the path: CurDir components and repeated
+    // separators are removed, and each ParentDir cancels out the Normal
+    // component before it. A ParentDir that would climb above the prefix or
+    // root is dropped, but leading ParentDirs of a relative path are kept,
+    // including a drive relative one like C:..\x. An empty relative result
+    // becomes .
+    //
+    // Verbatim paths (eg \\?\C:\x) are passed to the filesystem as is, so
+    // they are returned unchanged.
+    pub fn normalize(&self) -> Result<WindowsPathBuf, ParseError> {
+        let normalized = Normalized::new(&self.path)?;
+        if normalized.is_verbatim() {
+            return Ok(WindowsPathBuf::from(&self.path));
+        }
+        Ok(normalized.to_pathbuf(self.as_bytes().len()))
+    }
+
+    // The path that leads from base to this path, eg ..\..\x\y. Both paths
+    // are normalized first, and both must have the same prefix and either
+    // both have a root or neither does. Prefixes and names are compared
+    // case-insensitively.
+    pub fn relative_to<P: AsRef<OsStr> + ?Sized>(
+        &self,
+        base: &P,
+    ) -> Result<WindowsPathBuf, RelativePathError> {
+        let path = Normalized::new(&self.path)?;
+        let base = Normalized::new(SystemStr::new(base))?;
+        let same_prefix = match (path.prefix, base.prefix) {
+            (Some(a), Some(b)) => prefix_eq(a.kind(), b.kind()),
+            (None, None) => true,
+            _ => false,
+        };
+        if !same_prefix {
+            return Err(RelativePathError::PrefixMismatch);
+        }
+        if path.has_root != base.has_root {
+            return Err(RelativePathError::RootMismatch);
+        }
+
+        let common = path
+            .comps
+            .iter()
+            .zip(&base.comps)
+            .take_while(|(a, b)| same_name(a, b))
+            .count();
+        let base_rest = &base.comps[common..];
+        if base_rest.contains(&Component::ParentDir) {
+            return Err(RelativePathError::UnknownBase);
+        }
+
+        let mut comps = vec![Component::ParentDir; base_rest.len()];
+        comps.extend_from_slice(&path.comps[common..]);
+        let relative = Normalized {
+            prefix: None,
+            has_root: false,
+            comps,
+        };
+        Ok(relative.to_pathbuf(self.as_bytes().len()))
+    }
+
+    pub fn clean(&self) -> Result<WindowsPathBuf, ParseError> {
+        self.normalize()
+    }
+
+    pub fn join<P: AsRef<OsStr> + ?Sized>(&self, path: &P) -> WindowsPathBuf {
+        let mut pathbuf = WindowsPathBuf::from(&self.path);
+        pathbuf.push(path);
+        pathbuf
+    }
+}
+
 impl Deref for WindowsPath {
     type Target = SystemStr;
 
@@ -138,7 +900,7 @@ impl Deref for WindowsPath {
 
 impl AsSystemStr for &WindowsPath {
     fn as_sys_str(&self) -> &SystemStr {
-        &*self
+        self
     }
 }
 
@@ -153,11 +915,15 @@ impl<'path> Iterator for PathParts<'path, Iter<'path>> {
         }
 
         match self.path_iter().next() {
-            Some(Ok(c @ Component::Prefix(_))) => {
+            Some(Ok(c @ Component::Prefix(prefix))) => {
                 let mut cur = c.as_os_str().to_os_string();
                 match self.path_iter().next() {
+                    // A verbatim disk prefix already ends with the root
+                    Some(Ok(Component::RootDir(_)))
+                        if matches!(prefix.kind(), Prefix::VerbatimDisk(_)) => {
+                    }
                     Some(Ok(c @ Component::RootDir(_))) => {
-                        cur.push(c.as_os_str().to_os_string());
+                        cur.push(c.as_os_str());
                     }
                     Some(Ok(c)) => {
                         self.stored_item()
@@ -173,6 +939,26 @@ impl<'path> Iterator for PathParts<'path, Iter<'path>> {
     }
 }
 
+// --------------------
+// Ancestors
+// --------------------
+
+// Yields a path followed by each of its parents
+#[derive(Debug, Clone, Copy)]
+pub struct Ancestors<'path> {
+    next: Option<&'path WindowsPath>,
+}
+
+impl<'path> Iterator for Ancestors<'path> {
+    type Item = &'path WindowsPath;
+
+    fn next(&mut self) -> Option<&'path WindowsPath> {
+        let next = self.next?;
+        self.next = next.parent();
+        Some(next)
+    }
+}
+
 // --------------------
 // WindowsPathBuf
 // --------------------
@@ -186,6 +972,250 @@ impl WindowsPathBuf {
     pub fn new() -> WindowsPathBuf {
         Default::default()
     }
+
+    pub fn with_capacity(capacity: usize) -> WindowsPathBuf {
+        WindowsPathBuf {
+            pathbuf: SystemString::with_capacity(capacity),
+        }
+    }
+
+    // Follows the same rules as std::path::PathBuf::push on Windows:
+    //
+    // * a path with a prefix (eg C:\x, D:foo, \\server\share) replaces the
+    //   current path
+    // * a path with a root but no prefix replaces everything except the
+    //   current prefix
+    // * anything else is appended after a separator, except when the current
+    //   path is only a drive prefix (eg C:)
+    pub fn push<P: AsRef<OsStr> + ?Sized>(&mut self, path: &P) {
+        let path = SystemStr::new(path);
+        let new_root = PathRoot::new(path.as_bytes());
+        if new_root.prefix.is_some() {
+            self.pathbuf = SystemString::from(path);
+            return;
+        }
+
+        let cur_root = PathRoot::new(self.pathbuf.as_bytes());
+        if new_root.has_root {
+            self.pathbuf.truncate(cur_root.prefix_only_len());
+            self.pathbuf.push(path);
+            return;
+        }
+
+        let cur = self.pathbuf.as_bytes();
+        let drive_only = match cur_root.prefix {
+            Some(Prefix::Disk(_)) => cur_root.len == cur.len(),
+            _ => false,
+        };
+        let needs_sep = match cur.last() {
+            Some(&last) => !drive_only && Separator != last,
+            None => false,
+        };
+        if needs_sep {
+            self.pathbuf.push(r"\");
+        }
+        self.pathbuf.push(path);
+    }
+
+    // Removes the last component, returning false if there is only a prefix
+    // and/or root or nothing at all left to remove
+    pub fn pop(&mut self) -> bool {
+        match parent_len(self.pathbuf.as_bytes()) {
+            Some(len) => {
+                self.pathbuf.truncate(len);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn as_path(&self) -> &WindowsPath {
+        WindowsPath::new(&self.pathbuf)
+    }
+
+    pub fn parent(&self) -> Option<&WindowsPath> {
+        self.as_path().parent()
+    }
+
+    pub fn ancestors(&self) -> Ancestors<'_> {
+        self.as_path().ancestors()
+    }
+
+    pub fn tokens(&self) -> Tokens<'_> {
+        self.as_path().tokens()
+    }
+
+    pub fn validate_all(&self) -> Vec<ParseError> {
+        self.as_path().validate_all()
+    }
+
+    pub fn is_reserved(&self) -> bool {
+        self.as_path().is_reserved()
+    }
+
+    pub fn to_disk_path(&self) -> Option<WindowsPathBuf> {
+        self.as_path().to_disk_path()
+    }
+
+    pub fn simplify_verbatim(&self) -> WindowsPathBuf {
+        self.as_path().simplify_verbatim()
+    }
+
+    pub fn to_verbatim(&self) -> Result<WindowsPathBuf, ParseError> {
+        self.as_path().to_verbatim()
+    }
+
+    pub fn to_verbatim_with_limit(
+        &self,
+        limit: usize,
+    ) -> Result<WindowsPathBuf, ParseError> {
+        self.as_path().to_verbatim_with_limit(limit)
+    }
+
+    pub fn has_short_names(&self) -> bool {
+        self.as_path().has_short_names()
+    }
+
+    pub fn may_alias<P: AsRef<OsStr> + ?Sized>(&self, other: &P) -> bool {
+        self.as_path().may_alias(other)
+    }
+
+    pub fn file_stream(&self) -> Option<FileStream<'_>> {
+        self.as_path().file_stream()
+    }
+
+    pub fn as_posix(&self) -> SeparatorDisplay<'_> {
+        self.as_path().as_posix()
+    }
+
+    pub fn as_windows(&self) -> SeparatorDisplay<'_> {
+        self.as_path().as_windows()
+    }
+
+    pub fn to_unix(
+        &self,
+        policy: &ConvertPolicy,
+    ) -> Result<UnixPathBuf, ConvertError> {
+        self.as_path().to_unix(policy)
+    }
+
+    pub fn normalize(&self) -> Result<WindowsPathBuf, ParseError> {
+        self.as_path().normalize()
+    }
+
+    pub fn clean(&self) -> Result<WindowsPathBuf, ParseError> {
+        self.normalize()
+    }
+
+    pub fn relative_to<P: AsRef<OsStr> + ?Sized>(
+        &self,
+        base: &P,
+    ) -> Result<WindowsPathBuf, RelativePathError> {
+        self.as_path().relative_to(base)
+    }
+
+    pub fn join<P: AsRef<OsStr> + ?Sized>(&self, path: &P) -> WindowsPathBuf {
+        let mut pathbuf = self.clone();
+        pathbuf.push(path);
+        pathbuf
+    }
+
+    // Replaces the file name, or pushes name if there is no file name (eg
+    // the path is C:\ or ends in ..). Any trailing separators or curdir
+    // components after the old file name are dropped.
+    pub fn set_file_name<S: AsRef<OsStr> + ?Sized>(
+        &mut self,
+        name: &S,
+    ) -> Result<(), ParseError> {
+        let name = SystemStr::new(name);
+        validate_file_name(name)?;
+
+        match self.file_name_start() {
+            Some(start) => {
+                self.pathbuf.truncate(start);
+                self.pathbuf.push(name);
+            }
+            None => self.push(name),
+        }
+        Ok(())
+    }
+
+    // Replaces the extension of the file name, or removes it if ext is
+    // empty. Returns false without changing anything if there is no file
+    // name.
+    pub fn set_extension<S: AsRef<OsStr> + ?Sized>(
+        &mut self,
+        ext: &S,
+    ) -> Result<bool, ParseError> {
+        let mut name = match self.file_stem() {
+            Some(stem) => stem.to_os_string(),
+            None => return Ok(false),
+        };
+        let ext = ext.as_ref();
+        if !ext.is_empty() {
+            name.push(".");
+            name.push(ext);
+        }
+
+        self.set_file_name(&name)?;
+        Ok(true)
+    }
+
+    pub fn with_name<S: AsRef<OsStr> + ?Sized>(
+        &self,
+        name: &S,
+    ) -> Result<WindowsPathBuf, ParseError> {
+        let mut pathbuf = self.clone();
+        pathbuf.set_file_name(name)?;
+        Ok(pathbuf)
+    }
+
+    // Replaces the file stem, keeping the current extension
+    pub fn with_stem<S: AsRef<OsStr> + ?Sized>(
+        &self,
+        stem: &S,
+    ) -> Result<WindowsPathBuf, ParseError> {
+        let mut name = stem.as_ref().to_os_string();
+        if let Some(ext) = self.extension() {
+            name.push(".");
+            name.push(ext);
+        }
+        self.with_name(&name)
+    }
+
+    // Replaces the last suffix of the file name. The suffix must either be
+    // empty, which removes the current suffix, or start with a dot, and
+    // there must be a file name to add it to. The new file name must be
+    // valid, so eg a suffix cannot end in a dot or a space.
+    pub fn with_suffix<S: AsRef<OsStr> + ?Sized>(
+        &self,
+        suffix: &S,
+    ) -> Result<WindowsPathBuf, SuffixError> {
+        let suffix = SystemStr::new(suffix).as_bytes();
+        if !suffix.is_empty() && (suffix[0] != b'.' || suffix.len() == 1) {
+            let msg = String::from("suffix must be a dot followed by a name");
+            return Err(SuffixError::Parse(file_name_error(
+                WindowsErrorKind::InvalidCharacter,
+                suffix,
+                0,
+                suffix.len(),
+                msg,
+            )));
+        }
+
+        let mut name = match self.file_stem() {
+            Some(stem) => stem.to_os_string(),
+            None => return Err(SuffixError::NoFileName),
+        };
+        name.push(as_osstr(suffix));
+        Ok(self.with_name(&name)?)
+    }
+
+    // Byte offset of the file name within the path
+    fn file_name_start(&self) -> Option<usize> {
+        let name = SystemStr::new(self.file_name()?);
+        Some(offset_in(self.pathbuf.as_bytes(), name.as_bytes()))
+    }
 }
 
 impl Deref for WindowsPathBuf {
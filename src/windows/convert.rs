@@ -0,0 +1,583 @@
+// src/windows/convert.rs
+// Copyright (C) 2019 authors and contributors (see AUTHORS file)
+//
+// This file is released under the MIT License.
+
+// ===========================================================================
+// Imports
+// ===========================================================================
+
+// Stdlib imports
+use std::ffi::{OsStr, OsString};
+use std::ops::Range;
+
+// Third-party imports
+
+// Local imports
+use super::mount::MountTable;
+use super::path_type::ValidLastChar;
+use super::private_use::{
+    decode_private_use_bytes, private_use_byte, push_private_use,
+};
+use super::unc::is_valid_host;
+use super::{
+    is_reserved_name, Component, Iter, PrefixKind, WindowsErrorKind,
+    WindowsPath, WindowsPathBuf, RESTRICTED_CHARS,
+};
+use crate::common::error::{ConvertError, ParseError, ParseErrorKind};
+use crate::common::string::{as_osstr, ascii_uppercase};
+use crate::path::{PathIterator, SystemSeq, SystemStr};
+use crate::unix::{self, UnixErrorKind, UnixPath, UnixPathBuf};
+
+// ===========================================================================
+// ConvertPolicy
+// ===========================================================================
+
+// What the root of a Unix path is on Windows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RootMap {
+    // / is \, the root of the current drive
+    Rooted,
+
+    // / is the root of a drive, eg C:\ for Drive(b'C')
+    Drive(u8),
+
+    // / is the root of a UNC share, eg \\server\share\
+    Unc { server: OsString, share: OsString },
+
+    // Each Windows root is at a directory of the Unix filesystem, eg
+    // /mnt/c for C:\ under WSL
+    Mounts(MountTable),
+}
+
+// What to do with a name that is not valid in the target path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnInvalid {
+    Error,
+
+    // Replace the characters that stand in the way with %XX, where XX is
+    // the byte in hex. % itself is escaped on the way to Windows, so that
+    // escaped names are decoded back to the Unix name they came from. On
+    // the way to Unix every %XX that stands for a valid Unix character is
+    // decoded, so a Windows name that was not escaped to begin with may not
+    // convert back unchanged, eg C:\100%41 becomes /100A and then C:\100A.
+    Escape,
+
+    // Map the characters that stand in the way to the private use area, the
+    // way WSL and Cygwin do (see encode_private_use())
+    PrivateUse,
+}
+
+// Decides how UnixPath::to_windows() and WindowsPath::to_unix() map roots,
+// invalid characters and reserved names. By default / is mapped to \, and
+// anything that is not valid in the target is an error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConvertPolicy {
+    root: RootMap,
+    chars: OnInvalid,
+    reserved: OnInvalid,
+}
+
+impl ConvertPolicy {
+    pub fn new() -> ConvertPolicy {
+        ConvertPolicy {
+            root: RootMap::Rooted,
+            chars: OnInvalid::Error,
+            reserved: OnInvalid::Error,
+        }
+    }
+
+    pub fn with_root(mut self, root: RootMap) -> ConvertPolicy {
+        self.root = root;
+        self
+    }
+
+    // Applies to characters that one system does not allow in a name, eg
+    // : or a trailing . on Windows and / on Unix
+    pub fn on_invalid_char(mut self, action: OnInvalid) -> ConvertPolicy {
+        self.chars = action;
+        self
+    }
+
+    // Applies to names that Windows reserves for DOS devices, eg NUL.txt,
+    // whose first letter is encoded to make them ordinary names. With
+    // Escape, a % at the start of any name is escaped as well, so that it
+    // is not taken for an escaped letter on the way back.
+    pub fn on_reserved_name(mut self, action: OnInvalid) -> ConvertPolicy {
+        self.reserved = action;
+        self
+    }
+
+    pub fn root(&self) -> &RootMap {
+        &self.root
+    }
+}
+
+impl Default for ConvertPolicy {
+    fn default() -> Self {
+        ConvertPolicy::new()
+    }
+}
+
+// ===========================================================================
+// Unix to Windows
+// ===========================================================================
+
+pub(crate) fn unix_to_windows(
+    path: &UnixPath,
+    policy: &ConvertPolicy,
+) -> Result<WindowsPathBuf, ConvertError> {
+    // Iter yields CurDir for an empty path, which is kept empty instead
+    if path.as_bytes().is_empty() {
+        return Ok(WindowsPathBuf::new());
+    }
+
+    // An empty segment, eg between the slashes of a//b, comes back as CurDir
+    // and is dropped like the repeated separator it stands for
+    let comps = unix::Iter::new(path)
+        .spans()
+        .filter(|comp| match comp {
+            Ok((span, unix::Component::CurDir)) => !span.is_empty(),
+            _ => true,
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut ret: Vec<u8> = Vec::with_capacity(path.as_bytes().len() + 8);
+    let mut start = 0;
+    if let Some((_, unix::Component::RootDir)) = comps.first() {
+        let names: Vec<unix::Component> =
+            comps[1..].iter().map(|(_, comp)| *comp).collect();
+        start = 1 + push_windows_root(&mut ret, &policy.root, &names)?;
+    }
+
+    let mut needs_sep = !ret.is_empty() && !ret.ends_with(b"\\");
+    for (span, comp) in comps[start..].iter().cloned() {
+        if needs_sep {
+            ret.push(b'\\');
+        }
+
+        match comp {
+            unix::Component::Normal(name) => {
+                let name = SystemStr::new(name).as_bytes();
+                let name = windows_name(name, policy).map_err(|kind| {
+                    invalid_name(kind, path.as_os_str(), span)
+                })?;
+                ret.extend_from_slice(&name);
+            }
+            _ => ret.extend_from_slice(SystemStr::new(&comp).as_bytes()),
+        }
+        needs_sep = true;
+    }
+    Ok(WindowsPathBuf::from(as_osstr(&ret)))
+}
+
+// Adds the Windows root that root maps / to, and returns how many of the
+// components after / it stands for
+fn push_windows_root(
+    path: &mut Vec<u8>,
+    root: &RootMap,
+    comps: &[unix::Component],
+) -> Result<usize, ConvertError> {
+    match root {
+        RootMap::Rooted => {}
+        RootMap::Mounts(table) => {
+            let (len, root) = table
+                .windows_root(comps)
+                .ok_or(ConvertError::UnmappedRoot)?;
+            path.extend_from_slice(&root);
+            return Ok(len);
+        }
+        RootMap::Drive(letter) if letter.is_ascii_alphabetic() => {
+            path.extend_from_slice(&[ascii_uppercase(*letter), b':']);
+        }
+        RootMap::Unc { server, share } => {
+            let server = SystemStr::new(server).as_bytes();
+            let share = SystemStr::new(share).as_bytes();
+            let is_valid = is_valid_host(server)
+                && !share.is_empty()
+                && !share.iter().any(|b| RESTRICTED_CHARS.contains(b));
+            if !is_valid {
+                return Err(ConvertError::UnmappedRoot);
+            }
+            path.extend_from_slice(b"\\\\");
+            path.extend_from_slice(server);
+            path.push(b'\\');
+            path.extend_from_slice(share);
+        }
+        RootMap::Drive(_) => return Err(ConvertError::UnmappedRoot),
+    }
+    path.push(b'\\');
+    Ok(0)
+}
+
+// The Unix file name as a Windows one, or the kind of error that
+// stops it from being used
+fn windows_name(
+    name: &[u8],
+    policy: &ConvertPolicy,
+) -> Result<Vec<u8>, WindowsErrorKind> {
+    let last = name.len() - 1;
+    let invalid_last = name != ValidLastChar;
+    let escape_first = policy.reserved == OnInvalid::Escape;
+
+    let mut ret = Vec::with_capacity(name.len());
+    for (i, &b) in name.iter().enumerate() {
+        let invalid =
+            RESTRICTED_CHARS.contains(&b) || (i == last && invalid_last);
+        match policy.chars {
+            OnInvalid::Escape if invalid || b == ESCAPE => {
+                push_escaped(&mut ret, b)
+            }
+            _ if i == 0 && b == ESCAPE && escape_first => {
+                push_escaped(&mut ret, b)
+            }
+            OnInvalid::PrivateUse if invalid => push_private_use(&mut ret, b),
+            _ if invalid => return Err(WindowsErrorKind::InvalidCharacter),
+            _ => ret.push(b),
+        }
+    }
+
+    if is_reserved_name(&ret) {
+        if policy.reserved == OnInvalid::Error {
+            return Err(WindowsErrorKind::RestrictedName);
+        }
+
+        // Reserved names all start with an ascii letter, and escaping it is
+        // enough to make the name an ordinary one
+        let first = ret.remove(0);
+        let mut escaped = Vec::with_capacity(ret.len() + 3);
+        push_encoded(&mut escaped, first, policy.reserved);
+        escaped.append(&mut ret);
+        ret = escaped;
+    }
+    Ok(ret)
+}
+
+// ===========================================================================
+// Windows to Unix
+// ===========================================================================
+
+pub(crate) fn windows_to_unix(
+    path: &WindowsPath,
+    policy: &ConvertPolicy,
+) -> Result<UnixPathBuf, ConvertError> {
+    if path.as_bytes().is_empty() {
+        return Ok(UnixPathBuf::new());
+    }
+
+    let comps = Iter::new(path).spans().collect::<Result<Vec<_>, _>>()?;
+    let plain: Vec<Component> = comps.iter().map(|(_, comp)| *comp).collect();
+    let (start, mut ret) = unix_root(&plain, &policy.root)?;
+
+    let mut needs_sep = !ret.is_empty() && !ret.ends_with(b"/");
+    for (span, comp) in comps[start..].iter().cloned() {
+        if needs_sep {
+            ret.push(b'/');
+        }
+
+        match comp {
+            Component::Normal(name) => {
+                let name = SystemStr::new(name).as_bytes();
+                let name = unix_name(name, policy).map_err(|kind| {
+                    invalid_name(kind, path.as_os_str(), span)
+                })?;
+                ret.extend_from_slice(&name);
+            }
+            _ => ret.extend_from_slice(SystemStr::new(&comp).as_bytes()),
+        }
+        needs_sep = true;
+    }
+    Ok(UnixPathBuf::from(as_osstr(&ret)))
+}
+
+// The Unix root that the prefix and root of a path map to, along with how
+// many components of the path it stands for
+fn unix_root(
+    comps: &[Component],
+    root: &RootMap,
+) -> Result<(usize, Vec<u8>), ConvertError> {
+    match comps.first() {
+        Some(Component::Prefix(_)) | Some(Component::RootDir(_)) => {}
+        _ => return Ok((0, Vec::new())),
+    }
+
+    let found = match root {
+        RootMap::Mounts(table) => table.unix_root(comps),
+        RootMap::Rooted => match comps {
+            [Component::RootDir(_), ..] => Some((1, b"/".to_vec())),
+            _ => None,
+        },
+        _ => split_root(comps)
+            .filter(|&(kind, _)| prefix_matches(kind, root))
+            .map(|(_, len)| (len, b"/".to_vec())),
+    };
+    found.ok_or(ConvertError::UnmappedRoot)
+}
+
+// The prefix of an absolute path, and how many of its components the prefix
+// and root take up. A disk prefix must be followed by a root, since eg
+// C:dir is relative to the current directory of the drive, while other
+// prefixes are always absolute.
+pub(crate) fn split_root<'path>(
+    comps: &[Component<'path>],
+) -> Option<(PrefixKind<'path>, usize)> {
+    match comps {
+        [Component::Prefix(prefix), rest @ ..] => {
+            let kind = prefix.classify();
+            match (kind, rest.first()) {
+                (_, Some(Component::RootDir(_))) => Some((kind, 2)),
+                (PrefixKind::Disk(_), _) => None,
+                _ => Some((kind, 1)),
+            }
+        }
+        _ => None,
+    }
+}
+
+// Whether a prefix is the one that policy maps the Unix root to
+fn prefix_matches(kind: PrefixKind, root: &RootMap) -> bool {
+    match root {
+        RootMap::Drive(letter) => same_prefix(kind, PrefixKind::Disk(*letter)),
+        RootMap::Unc { server, share } => {
+            same_prefix(kind, PrefixKind::UNC(server, share))
+        }
+        _ => false,
+    }
+}
+
+// Whether two prefixes name the same drive or share, with or without a
+// verbatim prefix
+pub(crate) fn same_prefix(a: PrefixKind, b: PrefixKind) -> bool {
+    match (a, b) {
+        (PrefixKind::Disk(a), PrefixKind::Disk(b))
+        | (PrefixKind::Disk(a), PrefixKind::VerbatimDisk(b))
+        | (PrefixKind::VerbatimDisk(a), PrefixKind::Disk(b))
+        | (PrefixKind::VerbatimDisk(a), PrefixKind::VerbatimDisk(b)) => {
+            a.eq_ignore_ascii_case(&b)
+        }
+        (PrefixKind::UNC(a1, a2), PrefixKind::UNC(b1, b2))
+        | (PrefixKind::UNC(a1, a2), PrefixKind::VerbatimUNC(b1, b2))
+        | (PrefixKind::VerbatimUNC(a1, a2), PrefixKind::UNC(b1, b2))
+        | (PrefixKind::VerbatimUNC(a1, a2), PrefixKind::VerbatimUNC(b1, b2)) => {
+            eq_ignore_case(a1, b1) && eq_ignore_case(a2, b2)
+        }
+        _ => a == b,
+    }
+}
+
+fn eq_ignore_case(a: &OsStr, b: &OsStr) -> bool {
+    SystemStr::new(a)
+        .as_bytes()
+        .eq_ignore_ascii_case(SystemStr::new(b).as_bytes())
+}
+
+// The Windows file name as a Unix one, or the kind of error that stops
+// it from being used. Only names after a verbatim prefix can hold / or be
+// . or .., since they are not normalized.
+fn unix_name(
+    name: &[u8],
+    policy: &ConvertPolicy,
+) -> Result<Vec<u8>, UnixErrorKind> {
+    let action = policy.chars;
+    let name = decode_reserved(name, policy);
+    let name = match action {
+        OnInvalid::Error => name,
+        OnInvalid::Escape => unescape(&name),
+        OnInvalid::PrivateUse => decode_private_use_bytes(&name),
+    };
+    if name == b"." || name == b".." {
+        if action == OnInvalid::Error {
+            return Err(UnixErrorKind::RestrictedName);
+        }
+        let mut ret = Vec::with_capacity(name.len() * 3);
+        name.iter().for_each(|&b| push_encoded(&mut ret, b, action));
+        return Ok(ret);
+    }
+
+    let mut ret = Vec::with_capacity(name.len());
+    for b in name {
+        if is_unix_char(b) {
+            ret.push(b);
+        } else if action == OnInvalid::Error {
+            return Err(UnixErrorKind::InvalidCharacter);
+        } else {
+            push_encoded(&mut ret, b, action);
+        }
+    }
+    Ok(ret)
+}
+
+// Undoes the encoding of the first letter of a reserved name, unless
+// decoding the name with policy.chars undoes it anyway. The first byte is
+// only decoded if that gives a reserved name, or if it is a % that
+// windows_name() escaped.
+fn decode_reserved(name: &[u8], policy: &ConvertPolicy) -> Vec<u8> {
+    let first = match policy.reserved {
+        action if action == policy.chars => None,
+        OnInvalid::Error => None,
+        OnInvalid::Escape => escaped_byte(name),
+        OnInvalid::PrivateUse => private_use_byte(name),
+    };
+
+    if let Some(b) = first {
+        let mut decoded = Vec::with_capacity(name.len() - 2);
+        decoded.push(b);
+        decoded.extend_from_slice(&name[3..]);
+        let escaped = b == ESCAPE && policy.reserved == OnInvalid::Escape;
+        if escaped || is_reserved_name(&decoded) {
+            return decoded;
+        }
+    }
+    name.to_vec()
+}
+
+// ===========================================================================
+// Helpers
+// ===========================================================================
+
+const ESCAPE: u8 = b'%';
+
+fn is_unix_char(b: u8) -> bool {
+    b != b'/' && b != b'\0'
+}
+
+fn push_escaped(name: &mut Vec<u8>, b: u8) {
+    name.extend_from_slice(format!("%{:02X}", b).as_bytes());
+}
+
+// Adds b to name the way action escapes it, which must not be Error
+fn push_encoded(name: &mut Vec<u8>, b: u8, action: OnInvalid) {
+    match action {
+        OnInvalid::PrivateUse => push_private_use(name, b),
+        _ => push_escaped(name, b),
+    }
+}
+
+// Decodes each %XX of name that an escaped Unix name could hold, which is
+// any ascii byte that is allowed in a Unix name. Anything else is left as
+// it is.
+fn unescape(name: &[u8]) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(name.len());
+    let mut i = 0;
+    while i < name.len() {
+        match escaped_byte(&name[i..]) {
+            Some(b) if b.is_ascii() && is_unix_char(b) => {
+                ret.push(b);
+                i += 3;
+            }
+            _ => {
+                ret.push(name[i]);
+                i += 1;
+            }
+        }
+    }
+    ret
+}
+
+// The byte that the %XX at the start of name stands for
+fn escaped_byte(name: &[u8]) -> Option<u8> {
+    let hex = |b: u8| (b as char).to_digit(16);
+    match name.get(..3)? {
+        &[ESCAPE, hi, lo] => Some((hex(hi)? * 16 + hex(lo)?) as u8),
+        _ => None,
+    }
+}
+
+fn invalid_name<K: Into<ParseErrorKind>>(
+    kind: K,
+    path: &OsStr,
+    span: Range<usize>,
+) -> ConvertError {
+    let kind = kind.into();
+    let msg = match kind {
+        ParseErrorKind::Unix(UnixErrorKind::RestrictedName)
+        | ParseErrorKind::Windows(WindowsErrorKind::RestrictedName) => {
+            "component uses a name that is reserved in the target path"
+        }
+        _ => {
+            "component contains a character that is invalid in the target path"
+        }
+    };
+    let bytes = SystemStr::new(path).as_bytes();
+    ConvertError::InvalidName(ParseError::new(
+        kind,
+        as_osstr(&bytes[span.clone()]).to_os_string(),
+        path.to_os_string(),
+        span.start,
+        span.end,
+        String::from(msg),
+    ))
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escaped_names() {
+        let policy = ConvertPolicy::new()
+            .on_invalid_char(OnInvalid::Escape)
+            .on_reserved_name(OnInvalid::Escape);
+        let names: Vec<(&[u8], &[u8])> = vec![
+            (b"a:b", b"a%3Ab"),
+            (b"100%", b"100%25"),
+            (b"dots..", b"dots.%2E"),
+            (b"space ", b"space%20"),
+            (b"nul.txt", b"%6Eul.txt"),
+            (b"what?", b"what%3F"),
+        ];
+        for (unix, windows) in names {
+            assert_eq!(windows_name(unix, &policy).unwrap(), windows);
+            assert_eq!(unix_name(windows, &policy).unwrap(), unix);
+        }
+    }
+
+    #[test]
+    fn mixed_policies() {
+        let error = ConvertPolicy::new().on_reserved_name(OnInvalid::Escape);
+        let private_use = error.clone().on_invalid_char(OnInvalid::PrivateUse);
+        let names: Vec<(&ConvertPolicy, &[u8], &[u8])> = vec![
+            (&error, b"nul", b"%6Eul"),
+            (&error, b"%6Eul", b"%256Eul"),
+            (&error, b"%", b"%25"),
+            (&error, b"100%", b"100%"),
+            (&private_use, b"nul", b"%6Eul"),
+            (&private_use, b"%6Eul", b"%256Eul"),
+            (&private_use, b"a:b", "a\u{f03a}b".as_bytes()),
+        ];
+        for (policy, unix, windows) in names {
+            assert_eq!(windows_name(unix, policy).unwrap(), windows);
+            assert_eq!(unix_name(windows, policy).unwrap(), unix);
+        }
+
+        let escape = ConvertPolicy::new()
+            .on_invalid_char(OnInvalid::Escape)
+            .on_reserved_name(OnInvalid::PrivateUse);
+        let names: Vec<(&[u8], &[u8])> = vec![
+            (b"nul", "\u{f06e}ul".as_bytes()),
+            (b"a:b", b"a%3Ab"),
+            (b"%6Eul", b"%256Eul"),
+        ];
+        for (unix, windows) in names {
+            assert_eq!(windows_name(unix, &escape).unwrap(), windows);
+            assert_eq!(unix_name(windows, &escape).unwrap(), unix);
+        }
+
+        // Only an escaped first letter that makes a reserved name is decoded
+        assert_eq!(unix_name(b"%41bc", &error).unwrap(), b"%41bc");
+        assert_eq!(unix_name(b"%41ux", &error).unwrap(), b"Aux");
+    }
+
+    #[test]
+    fn unescape_leaves_invalid_bytes() {
+        assert_eq!(unescape(b"a%2Fb%00%4"), b"a%2Fb%00%4");
+        assert_eq!(unescape(b"%zz%41"), b"%zzA");
+    }
+}
+
+// ===========================================================================
+//
+// ===========================================================================
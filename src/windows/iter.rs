@@ -15,12 +15,12 @@ pub mod parser;
 
 #[cfg(feature = "manual-iter")]
 mod iter_imports {
-    pub use super::manual::{Iter, PathComponent, Prefix};
+    pub use super::manual::{Iter, PathComponent};
 }
 
 #[cfg(all(feature = "parser-iter", not(feature = "manual-iter")))]
 mod iter_imports {
-    pub use super::parser::{Iter, PathComponent, Prefix};
+    pub use super::parser::{Iter, PathComponent};
 }
 
 // ===========================================================================
@@ -29,12 +29,17 @@ mod iter_imports {
 
 // Stdlib imports
 use std::ffi::OsStr;
+use std::ops::Range;
 
 // Third-party imports
 
 // Local imports
+use crate::common::error::ParseError;
 use crate::common::string::as_osstr;
-use crate::path::SystemStr;
+use crate::path::{PathIterator, SystemSeq, SystemStr};
+use crate::windows::is_component_separator;
+use crate::windows::prefix::PrefixKind;
+use crate::windows::unc::{UncHost, UncShare};
 
 // ===========================================================================
 // Re-exports
@@ -47,7 +52,7 @@ pub use std::path::Prefix;
 // Iter
 // ===========================================================================
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum Component<'path> {
     Prefix(PrefixComponent<'path>),
     RootDir(&'path OsStr),
@@ -81,7 +86,7 @@ impl<'path> AsRef<SystemStr> for Component<'path> {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub struct PrefixComponent<'path> {
     raw: &'path OsStr,
     parsed: Prefix<'path>,
@@ -102,6 +107,215 @@ impl<'path> PrefixComponent<'path> {
     pub fn as_os_str(&self) -> &'path OsStr {
         self.raw
     }
+
+    // The server of a UNC prefix, or None if it has no server or the server
+    // is not a valid host
+    pub fn unc_host(&self) -> Option<UncHost<'path>> {
+        match self.parsed {
+            Prefix::UNC(server, _) | Prefix::VerbatimUNC(server, _) => {
+                UncHost::parse(server)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn unc_share(&self) -> Option<UncShare<'path>> {
+        match self.parsed {
+            Prefix::UNC(_, share) | Prefix::VerbatimUNC(_, share) => {
+                Some(UncShare::parse(share))
+            }
+            _ => None,
+        }
+    }
+
+    // A finer classification of the prefix than kind(), which tells apart
+    // eg pipes and volume GUID paths
+    pub fn classify(&self) -> PrefixKind<'path> {
+        let raw = SystemStr::new(self.raw).as_bytes();
+        PrefixKind::classify(raw, self.parsed)
+    }
+}
+
+// ===========================================================================
+// Spans
+// ===========================================================================
+
+pub type SpannedComponent<'path> =
+    Result<(Range<usize>, Component<'path>), ParseError>;
+
+// Yields each component along with the range of bytes of the path that it
+// was parsed from. An error already records where it was found.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Spans<'path> {
+    iter: Iter<'path>,
+}
+
+impl<'path> Iter<'path> {
+    pub fn spans(self) -> Spans<'path> {
+        Spans { iter: self }
+    }
+}
+
+impl<'path> Iterator for Spans<'path> {
+    type Item = SpannedComponent<'path>;
+
+    fn next(&mut self) -> Option<SpannedComponent<'path>> {
+        let comp = self.iter.next()?;
+        Some(comp.map(|c| (self.iter.span(), c)))
+    }
+}
+
+impl<'path> DoubleEndedIterator for Spans<'path> {
+    fn next_back(&mut self) -> Option<SpannedComponent<'path>> {
+        let comp = self.iter.next_back()?;
+        Some(comp.map(|c| (self.iter.span(), c)))
+    }
+}
+
+// ===========================================================================
+// Tokens
+// ===========================================================================
+
+// A piece of a path as it was written. Unlike Component, separators and
+// empty segments are kept, so concatenating every Token of a path gives the
+// path back exactly.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Token<'path> {
+    // For a verbatim disk prefix this leaves out the separator that Iter
+    // also returns as the root
+    Prefix(&'path OsStr),
+    RootDir(&'path OsStr),
+    Separator(&'path OsStr),
+
+    // The segment between two consecutive separators
+    Empty,
+    Normal(&'path OsStr),
+}
+
+impl<'path> Token<'path> {
+    pub fn as_os_str(&self) -> &'path OsStr {
+        match self {
+            Token::Prefix(part)
+            | Token::RootDir(part)
+            | Token::Separator(part)
+            | Token::Normal(part) => part,
+            Token::Empty => OsStr::new(""),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum TokenState {
+    Start,
+    Root,
+    Segment,
+    Separator,
+    Finish,
+}
+
+// Splits a path into Tokens. The prefix and root are found the same way
+// that Iter finds them, but no segment is validated, so use Iter to check
+// the path.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Tokens<'path> {
+    path: &'path [u8],
+    state: TokenState,
+    cur: usize,
+    has_root: bool,
+    comp_start: usize,
+    verbatim: bool,
+}
+
+impl<'path> PathIterator<'path> for Tokens<'path> {
+    fn new(path: &'path SystemStr) -> Tokens<'path> {
+        let iter = Iter::new(path);
+        Tokens {
+            path: path.as_ref(),
+            state: TokenState::Start,
+            cur: 0,
+            has_root: iter.has_root(),
+            comp_start: iter.components_start(),
+            verbatim: iter.is_verbatim(),
+        }
+    }
+}
+
+impl<'path> Tokens<'path> {
+    fn next_prefix(&mut self) -> Option<Token<'path>> {
+        self.state = TokenState::Root;
+        let end = if self.has_root {
+            self.comp_start - 1
+        } else {
+            self.comp_start
+        };
+
+        if end > 0 {
+            self.cur = end;
+            Some(Token::Prefix(as_osstr(&self.path[..end])))
+        } else {
+            self.next_root()
+        }
+    }
+
+    fn next_root(&mut self) -> Option<Token<'path>> {
+        self.state = TokenState::Segment;
+        if self.has_root {
+            let (start, end) = (self.cur, self.comp_start);
+            self.cur = end;
+            Some(Token::RootDir(as_osstr(&self.path[start..end])))
+        } else {
+            self.next_segment()
+        }
+    }
+
+    fn next_segment(&mut self) -> Option<Token<'path>> {
+        let (path, start) = (self.path, self.cur);
+        if start >= path.len() {
+            self.state = TokenState::Finish;
+            return None;
+        }
+
+        let verbatim = self.verbatim;
+        let sep = path[start..]
+            .iter()
+            .position(|&b| is_component_separator(b, verbatim));
+        let end = match sep {
+            Some(i) => {
+                self.state = TokenState::Separator;
+                start + i
+            }
+            None => {
+                self.state = TokenState::Finish;
+                path.len()
+            }
+        };
+        self.cur = end;
+
+        if start == end {
+            Some(Token::Empty)
+        } else {
+            Some(Token::Normal(as_osstr(&path[start..end])))
+        }
+    }
+}
+
+impl<'path> Iterator for Tokens<'path> {
+    type Item = Token<'path>;
+
+    fn next(&mut self) -> Option<Token<'path>> {
+        match self.state {
+            TokenState::Start => self.next_prefix(),
+            TokenState::Root => self.next_root(),
+            TokenState::Segment => self.next_segment(),
+            TokenState::Separator => {
+                let start = self.cur;
+                self.cur += 1;
+                self.state = TokenState::Segment;
+                Some(Token::Separator(as_osstr(&self.path[start..self.cur])))
+            }
+            TokenState::Finish => None,
+        }
+    }
 }
 
 // ===========================================================================
@@ -9,6 +9,7 @@
 
 // Stdlib imports
 use std::ffi::{OsStr, OsString};
+use std::ops::Range;
 
 // Third-party imports
 
@@ -18,9 +19,12 @@ use crate::common::error::ParseError;
 use crate::common::string::{as_osstr, as_str};
 use crate::path::{PathIterator, SystemStr};
 use crate::windows::{
+    components_end, is_component_separator,
     match_prefix::match_prefix,
-    path_type::{Device, NonDevicePart},
-    WindowsErrorKind, SEPARATOR,
+    path_type::{Device, NonDevicePart, VerbatimPart},
+    stream::{is_valid_stream, stream_start},
+    unc::invalid_host_end,
+    BackParseState, WindowsErrorKind,
 };
 
 // ===========================================================================
@@ -52,21 +56,127 @@ pub type PathComponent<'path> = Result<Component<'path>, ParseError>;
 pub struct Iter<'path> {
     path: &'path [u8],
     parse_state: PathParseState,
+    back_state: BackParseState,
     cur: usize,
+
+    // The components after the prefix and root lie between comp_start and
+    // end, and next_back() moves end back towards cur as it parses them
+    has_root: bool,
+    comp_start: usize,
+    end: usize,
+
+    // Bytes of path that the last component returned from either end was
+    // parsed from
+    span: Range<usize>,
+
+    // Whether to carry on with the next component after an invalid one
+    recover: bool,
+
+    // Whether the last component can name an NTFS alternate data stream,
+    // eg file.txt:Zone.Identifier:$DATA
+    streams: bool,
+
+    // Whether the path has a verbatim prefix, which turns off the
+    // normalization of the components after it
+    verbatim: bool,
+
+    // Where the server of a path like \\bad host\share ends, until the
+    // error for it has been returned
+    invalid_host: Option<usize>,
 }
 
 impl<'path> PathIterator<'path> for Iter<'path> {
-    fn new(path: &'path SystemStr) -> Iter {
+    fn new(path: &'path SystemStr) -> Iter<'path> {
+        let path: &[u8] = path.as_ref();
+        let (prefix_end, verbatim, verbatimdisk) = match match_prefix(path) {
+            Some((end, prefix @ Prefix::VerbatimDisk(_))) => {
+                (end, prefix.is_verbatim(), true)
+            }
+            Some((end, prefix)) => (end, prefix.is_verbatim(), false),
+            None => (0, false, false),
+        };
+
+        let invalid_host = match prefix_end {
+            0 => invalid_host_end(path),
+            _ => None,
+        };
+
+        // This mirrors parse_root()
+        let has_more = prefix_end < path.len();
+        let is_root =
+            has_more && is_component_separator(path[prefix_end], verbatim);
+        let comp_start = if is_root { prefix_end + 1 } else { prefix_end };
+
         Iter {
-            path: path.as_ref(),
+            path,
             parse_state: PathParseState::Start,
+            back_state: BackParseState::PathComponent,
             cur: 0,
+            has_root: has_more && (verbatimdisk || is_root),
+            comp_start,
+            end: components_end(path, comp_start, verbatim),
+            span: 0..0,
+            recover: false,
+            streams: false,
+            verbatim,
+            invalid_host,
         }
     }
 }
 
 impl<'path> Iter<'path> {
+    // Makes the iterator return an error for each invalid component and then
+    // continue with the next one, instead of stopping at the first error
+    pub fn recovering(mut self) -> Iter<'path> {
+        self.recover = true;
+        self
+    }
+
+    // Makes the iterator accept a stream name and type after the last
+    // component, which is otherwise rejected since : is not valid in a name
+    pub fn with_streams(mut self) -> Iter<'path> {
+        self.streams = true;
+        self
+    }
+
+    // Where the stream of the component that starts at start begins, if
+    // streams are allowed and it is the last component of the path
+    fn stream_colon(&self, start: usize) -> Option<usize> {
+        if !self.streams {
+            return None;
+        }
+        let part = &self.path[start..];
+        if part.iter().any(|&b| self.is_separator(b)) {
+            return None;
+        }
+        stream_start(part).map(|i| start + i)
+    }
+
+    // Stops the iteration at an invalid component, unless the iterator is
+    // recovering from errors
+    fn stop_at_error(&mut self) {
+        if !self.recover {
+            self.parse_state = PathParseState::Finish;
+        }
+    }
+
+    // Returns the error for the server of a path like \\bad host\share,
+    // which ends the iteration from either end since no component of the
+    // path can be trusted
+    fn parse_invalid_host(&mut self) -> Option<PathComponent<'path>> {
+        let end = self.invalid_host.take()?;
+        self.parse_state = PathParseState::Finish;
+        self.span = 2..end;
+
+        let msg = String::from("server is not a valid host name or address");
+        Some(self.build_error(WindowsErrorKind::InvalidHost, 2, end, msg))
+    }
+
     fn parse_prefix(&mut self) -> Option<PathComponent<'path>> {
+        if let Some(err) = self.parse_invalid_host() {
+            return Some(err);
+        }
+
         let mut verbatimdisk = false;
         let mut ret = None;
         if let Some((end, prefix)) = match_prefix(self.path) {
@@ -75,6 +185,7 @@ impl<'path> Iter<'path> {
             }
             let prefix_comp = PrefixComponent::new(&self.path[..end], prefix);
             self.cur = end;
+            self.span = 0..end;
 
             ret = Some(Ok(Component::Prefix(prefix_comp)));
         }
@@ -94,17 +205,19 @@ impl<'path> Iter<'path> {
     ) -> Option<PathComponent<'path>> {
         let path_len = self.path.len();
         let cur = self.cur;
-        if path_len == 0 {
-            self.parse_state = PathParseState::PathComponent;
-            return Some(Ok(Component::CurDir));
-        } else if cur == path_len {
+
+        // Either the path is only a prefix, or next_back() has already
+        // parsed everything after the prefix
+        if (path_len > 0 && cur == path_len)
+            || self.back_state == BackParseState::Prefix
+        {
             self.parse_state = PathParseState::Finish;
             return None;
         }
 
         self.parse_state = PathParseState::Root;
 
-        let is_root = SEPARATOR.contains(&self.path[self.cur]);
+        let is_root = cur < path_len && self.is_separator(self.path[cur]);
         if is_root {
             self.cur += 1;
         }
@@ -112,6 +225,7 @@ impl<'path> Iter<'path> {
         if verbatimdisk || is_root {
             let end = self.cur;
             let start = end - 1;
+            self.span = start..end;
             let ret = Component::RootDir(as_osstr(&self.path[start..end]));
             return Some(Ok(ret));
         }
@@ -120,42 +234,105 @@ impl<'path> Iter<'path> {
     }
 
     fn parse_component(&mut self) -> Option<PathComponent<'path>> {
-        let end = self.path.len();
         let cur = self.cur;
-
-        if cur == end {
+        if cur >= self.end {
             self.parse_state = PathParseState::Finish;
             return None;
         }
 
-        let mut ret = None;
-        for i in cur..end {
-            let cur_char = &self.path[i];
-            if SEPARATOR.contains(cur_char) {
-                let part = &self.path[cur..i];
-                let comp = if part.is_empty() {
-                    Ok(Component::CurDir)
-                } else {
-                    self.build_comp(cur, i)
-                };
-                ret = Some(comp);
-                self.cur = i + 1;
-                break;
-            }
-        }
-
         match self.parse_state {
             PathParseState::Finish | PathParseState::PathComponent => {}
             _ => self.parse_state = PathParseState::PathComponent,
         }
 
-        match ret {
-            Some(_) => ret,
+        let end = self.end.min(self.path.len());
+        let sep = self.path[cur..end]
+            .iter()
+            .position(|&b| self.is_separator(b));
+        match sep {
+            Some(i) => {
+                self.cur = cur + i + 1;
+                Some(self.parse_part(cur, cur + i))
+            }
+
+            // This is the last component
             None => {
-                let comp = self.build_comp(cur, end);
                 self.cur = end;
-                Some(comp)
+                self.end = end;
+                Some(self.parse_part(cur, end))
+            }
+        }
+    }
+
+    fn parse_component_back(&mut self) -> Option<PathComponent<'path>> {
+        let start = self.cur.max(self.comp_start);
+        if start >= self.end {
+            self.back_state = BackParseState::Root;
+            return self.parse_root_back();
+        }
+
+        // The last component ends just before its separator
+        let end = self.end - 1;
+        let sep = self.path[start..end]
+            .iter()
+            .rposition(|&b| self.is_separator(b));
+        match sep {
+            Some(i) => {
+                self.end = start + i + 1;
+                Some(self.parse_part(self.end, end))
+            }
+
+            // This is the first component that is left
+            None => {
+                self.end = start;
+                Some(self.parse_part(start, end))
+            }
+        }
+    }
+
+    fn parse_root_back(&mut self) -> Option<PathComponent<'path>> {
+        self.back_state = BackParseState::Prefix;
+
+        let root_left = match self.parse_state {
+            PathParseState::Start | PathParseState::Prefix { .. } => {
+                self.has_root
             }
+            _ => false,
+        };
+        if !root_left {
+            return self.parse_prefix_back();
+        }
+
+        // The root is always the single byte before the components, even
+        // when it overlaps with a verbatim disk prefix
+        let end = self.comp_start;
+        self.span = end - 1..end;
+        let ret = Component::RootDir(as_osstr(&self.path[end - 1..end]));
+        Some(Ok(ret))
+    }
+
+    fn parse_prefix_back(&mut self) -> Option<PathComponent<'path>> {
+        // Nothing else is left once the prefix is reached
+        let prefix_left = self.parse_state == PathParseState::Start;
+        self.parse_state = PathParseState::Finish;
+
+        if !prefix_left {
+            return None;
+        }
+        let (end, prefix) = match_prefix(self.path)?;
+        self.span = 0..end;
+        let prefix_comp = PrefixComponent::new(&self.path[..end], prefix);
+        Some(Ok(Component::Prefix(prefix_comp)))
+    }
+
+    // Parses the component between start and end, which both ends of the
+    // iterator use so that they report the same errors
+    fn parse_part(&mut self, start: usize, end: usize) -> PathComponent<'path> {
+        self.span = start..end;
+        if start == end {
+            Ok(Component::CurDir)
+        } else {
+            self.build_comp(start, end)
         }
     }
 
@@ -164,9 +341,22 @@ impl<'path> Iter<'path> {
         start: usize,
         end: usize,
     ) -> Result<Component<'path>, ParseError> {
+        // The base name before a stream is built on its own, as a part that
+        // ends at the stream's :
+        let stream = self.stream_colon(start).filter(|&colon| colon < end);
+        if let Some(colon) = stream {
+            return self.build_stream_comp(start, colon, end);
+        }
+
         let part = &self.path[start..end];
-        if part != NonDevicePart {
-            if part == Device {
+        let is_valid = if self.verbatim {
+            part == VerbatimPart
+        } else {
+            part == NonDevicePart
+        };
+
+        if !is_valid {
+            if !self.verbatim && part == Device {
                 self.invalid_name(start, end)
             } else {
                 self.invalid_char(start, end)
@@ -174,21 +364,58 @@ impl<'path> Iter<'path> {
         } else {
             let comp_str = as_str(part);
             let ret = match comp_str {
-                "." => Component::CurDir,
-                ".." => Component::ParentDir,
+                // These are ordinary names in a verbatim path
+                "." if !self.verbatim => Component::CurDir,
+                ".." if !self.verbatim => Component::ParentDir,
                 _ => Component::Normal(OsStr::new(comp_str)),
             };
             Ok(ret)
         }
     }
 
+    // Checks the stream after colon and the base name before it on its own
+    fn build_stream_comp(
+        &mut self,
+        start: usize,
+        colon: usize,
+        end: usize,
+    ) -> Result<Component<'path>, ParseError> {
+        if !is_valid_stream(&self.path[colon + 1..end]) {
+            self.stop_at_error();
+            let msg = String::from("path component has an invalid stream");
+            return self.build_error(
+                WindowsErrorKind::InvalidCharacter,
+                colon,
+                end,
+                msg,
+            );
+        }
+
+        match self.parse_part(start, colon)? {
+            Component::Normal(_) => {
+                self.span = start..end;
+                Ok(Component::Normal(as_osstr(&self.path[start..end])))
+            }
+            _ => {
+                self.stop_at_error();
+                let msg =
+                    String::from("path component has an invalid base name");
+                self.build_error(
+                    WindowsErrorKind::InvalidCharacter,
+                    start,
+                    end,
+                    msg,
+                )
+            }
+        }
+    }
+
     fn invalid_name(
         &mut self,
         start: usize,
         end: usize,
     ) -> Result<Component<'path>, ParseError> {
-        // Return None for every call to next() after this
-        self.parse_state = PathParseState::Finish;
+        self.stop_at_error();
 
         let msg = String::from("component uses a restricted name");
         self.build_error(WindowsErrorKind::RestrictedName, start, end, msg)
@@ -199,8 +426,7 @@ impl<'path> Iter<'path> {
         start: usize,
         end: usize,
     ) -> Result<Component<'path>, ParseError> {
-        // Return None for every call to next() after this
-        self.parse_state = PathParseState::Finish;
+        self.stop_at_error();
         let msg = String::from("path component contains an invalid character");
         self.build_error(WindowsErrorKind::InvalidCharacter, start, end, msg)
     }
@@ -217,14 +443,35 @@ impl<'path> Iter<'path> {
             kind.into(),
             OsString::from(part),
             OsString::from(as_str(self.path)),
-            self.cur,
-            self.cur + part.len(),
+            start,
+            end,
             msg,
         );
 
         Err(err)
     }
 
+    pub(crate) fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    // Where the components that follow the prefix and root begin
+    pub(crate) fn components_start(&self) -> usize {
+        self.comp_start
+    }
+
+    pub(crate) fn has_root(&self) -> bool {
+        self.has_root
+    }
+
+    pub(crate) fn is_verbatim(&self) -> bool {
+        self.verbatim
+    }
+
+    fn is_separator(&self, byte: u8) -> bool {
+        is_component_separator(byte, self.verbatim)
+    }
+
     #[cfg(test)]
     pub fn current_index(&self) -> usize {
         self.cur
@@ -248,6 +495,23 @@ impl<'path> Iterator for Iter<'path> {
     }
 }
 
+impl<'path> DoubleEndedIterator for Iter<'path> {
+    fn next_back(&mut self) -> Option<PathComponent<'path>> {
+        if self.parse_state == PathParseState::Finish {
+            return None;
+        }
+        if let Some(err) = self.parse_invalid_host() {
+            return Some(err);
+        }
+
+        match self.back_state {
+            BackParseState::PathComponent => self.parse_component_back(),
+            BackParseState::Root => self.parse_root_back(),
+            BackParseState::Prefix => self.parse_prefix_back(),
+        }
+    }
+}
+
 impl<'path> AsRef<SystemStr> for Iter<'path> {
     fn as_ref(&self) -> &SystemStr {
         SystemStr::from_bytes(&self.path[self.cur..])
@@ -8,6 +8,7 @@
 // ===========================================================================
 
 // Stdlib imports
+use std::ops::Range;
 
 // Third-party imports
 use combine::{
@@ -20,11 +21,17 @@ use combine::{
 use crate::common::error;
 use crate::common::string::as_osstr;
 use crate::path::{PathIterator, SystemStr};
-use crate::windows::iter::Component;
+use crate::windows::iter::{Component, PrefixComponent};
 use crate::windows::parser::{
-    component, prefix, root, valid_part_char, RESTRICTED_NAME_ERRMSG,
+    component, prefix, root, valid_part_char, verbatim_component,
+    verbatim_root, RESTRICTED_NAME_ERRMSG,
+};
+use crate::windows::prefix::match_object_prefix;
+use crate::windows::stream::{is_valid_stream, stream_start};
+use crate::windows::unc::invalid_host_end;
+use crate::windows::{
+    components_end, is_component_separator, BackParseState, WindowsErrorKind,
 };
-use crate::windows::WindowsErrorKind;
 
 // ===========================================================================
 // Re-exports
@@ -46,6 +53,55 @@ enum PathParseState {
     Finish,
 }
 
+type ComponentResult<'path> = Result<
+    ((PathComponent<'path>, usize), &'path [u8]),
+    Errors<u8, &'path [u8], PointerOffset>,
+>;
+
+// ===========================================================================
+// Helpers
+// ===========================================================================
+
+// Parses the prefix at the start of path. The prefixes of NT object paths
+// are found before the parsers get a chance to split them up.
+fn prefix_at(path: &[u8]) -> Option<(PrefixComponent<'_>, usize)> {
+    if let Some((end, kind)) = match_object_prefix(path) {
+        let comp = PrefixComponent::new(&path[..end], kind);
+
+        // Like the parser, leave the separator of a verbatim disk prefix for
+        // the root
+        let len = match kind {
+            Prefix::VerbatimDisk(_) => end - 1,
+            _ => end,
+        };
+        return Some((comp, len));
+    }
+
+    match prefix().easy_parse(path) {
+        Ok(((Ok(Component::Prefix(comp)), len), _)) => Some((comp, len)),
+        _ => None,
+    }
+}
+
+// Parses the root at the start of path, which can only be \\ after a
+// verbatim prefix
+fn root_at(path: &[u8], verbatim: bool) -> Option<(PathComponent<'_>, usize)> {
+    let result = if verbatim {
+        verbatim_root().easy_parse(path)
+    } else {
+        root().easy_parse(path)
+    };
+    result.ok().map(|(root, _)| root)
+}
+
+fn component_at(path: &[u8], verbatim: bool) -> ComponentResult<'_> {
+    if verbatim {
+        verbatim_component().easy_parse(path)
+    } else {
+        component().easy_parse(path)
+    }
+}
+
 // ===========================================================================
 // Iter
 // ===========================================================================
@@ -54,34 +110,138 @@ enum PathParseState {
 pub struct Iter<'path> {
     path: &'path [u8],
     parse_state: PathParseState,
+    back_state: BackParseState,
     cur: usize,
+
+    // The components after the prefix and root lie between comp_start and
+    // end, and next_back() moves end back towards cur as it parses them
+    has_root: bool,
+    comp_start: usize,
+    end: usize,
+
+    // Bytes of path that the last component returned from either end was
+    // parsed from
+    span: Range<usize>,
+
+    // Whether to carry on with the next component after an invalid one
+    recover: bool,
+
+    // Whether the last component can name an NTFS alternate data stream,
+    // eg file.txt:Zone.Identifier:$DATA
+    streams: bool,
+
+    // Whether the path has a verbatim prefix, which turns off the
+    // normalization of the components after it
+    verbatim: bool,
+
+    // Where the server of a path like \\bad host\share ends, until the
+    // error for it has been returned
+    invalid_host: Option<usize>,
 }
 
 impl<'path> PathIterator<'path> for Iter<'path> {
-    fn new(path: &SystemStr) -> Iter {
-        let p = path.as_ref();
+    fn new(path: &SystemStr) -> Iter<'_> {
+        let p: &[u8] = path.as_ref();
+        let (prefix_end, verbatim) = match prefix_at(p) {
+            Some((prefix, end)) => (end, prefix.kind().is_verbatim()),
+            None => (0, false),
+        };
+        let invalid_host = match prefix_end {
+            0 => invalid_host_end(p),
+            _ => None,
+        };
+        let root_len = match root_at(&p[prefix_end..], verbatim) {
+            Some((_, len)) => len,
+            None => 0,
+        };
+        let comp_start = prefix_end + root_len;
+
         Iter {
             path: p,
             parse_state: PathParseState::Start,
+            back_state: BackParseState::PathComponent,
             cur: 0,
+            has_root: root_len > 0,
+            comp_start,
+            end: components_end(p, comp_start, verbatim),
+            span: 0..0,
+            recover: false,
+            streams: false,
+            verbatim,
+            invalid_host,
         }
     }
 }
 
 impl<'path> Iter<'path> {
+    // Makes the iterator return an error for each invalid component and then
+    // continue with the next one, instead of stopping at the first error
+    pub fn recovering(mut self) -> Iter<'path> {
+        self.recover = true;
+        self
+    }
+
+    // Makes the iterator accept a stream name and type after the last
+    // component, which is otherwise rejected since : is not valid in a name
+    pub fn with_streams(mut self) -> Iter<'path> {
+        self.streams = true;
+        self
+    }
+
+    // Where the stream of the component that starts at start begins, if
+    // streams are allowed and it is the last component of the path
+    fn stream_colon(&self, start: usize) -> Option<usize> {
+        if !self.streams {
+            return None;
+        }
+        let part = &self.path[start..];
+        if part
+            .iter()
+            .any(|&b| is_component_separator(b, self.verbatim))
+        {
+            return None;
+        }
+        stream_start(part).map(|i| start + i)
+    }
+
+    // Stops the iteration at an invalid component, unless the iterator is
+    // recovering from errors
+    fn stop_at_error(&mut self) {
+        if !self.recover {
+            self.parse_state = PathParseState::Finish;
+        }
+    }
+
+    // Returns the error for the server of a path like \\bad host\share,
+    // which ends the iteration from either end since no component of the
+    // path can be trusted
+    fn parse_invalid_host(&mut self) -> Option<PathComponent<'path>> {
+        let end = self.invalid_host.take()?;
+        self.parse_state = PathParseState::Finish;
+        self.span = 2..end;
+
+        Some(Err(error::ParseError::new(
+            WindowsErrorKind::InvalidHost.into(),
+            as_osstr(&self.path[2..end]).into(),
+            as_osstr(self.path).into(),
+            2,
+            end,
+            String::from("server is not a valid host name or address"),
+        )))
+    }
+
     fn parse_prefix(&mut self) -> Option<PathComponent<'path>> {
-        // This case will only happen if the input path is empty
-        if self.path.is_empty() {
-            self.parse_state = PathParseState::PathComponent;
-            return Some(Ok(Component::CurDir));
+        if let Some(err) = self.parse_invalid_host() {
+            return Some(err);
         }
 
         let mut ret = None;
-        if let Ok((found, _)) = prefix().easy_parse(self.path) {
-            if let (Ok(Component::Prefix(_)), end) = found {
-                self.cur = end;
-                ret = Some(found.0);
-            }
+        if let Some((prefix, end)) = prefix_at(self.path) {
+            // The parsed length of a verbatim disk prefix leaves out its
+            // separator, which is still part of the prefix
+            self.span = 0..prefix.as_os_str().len();
+            self.cur = end;
+            ret = Some(Ok(Component::Prefix(prefix)));
         }
 
         self.parse_state = PathParseState::Prefix;
@@ -93,10 +253,17 @@ impl<'path> Iter<'path> {
     }
 
     fn parse_root(&mut self) -> Option<PathComponent<'path>> {
+        // next_back() has already parsed everything after the prefix
+        if self.back_state == BackParseState::Prefix {
+            self.parse_state = PathParseState::Finish;
+            return None;
+        }
+
         self.parse_state = PathParseState::Root;
         let path = &self.path[self.cur..];
 
-        if let Ok(((comp, len), _)) = root().easy_parse(path) {
+        if let Some((comp, len)) = root_at(path, self.verbatim) {
+            self.span = self.cur..self.cur + len;
             self.cur += len;
             Some(comp)
         } else {
@@ -105,34 +272,172 @@ impl<'path> Iter<'path> {
     }
 
     fn parse_component(&mut self) -> Option<PathComponent<'path>> {
-        let end = self.path.len();
         let cur = self.cur;
-
-        if cur == end {
+        if cur >= self.end {
             self.parse_state = PathParseState::Finish;
             return None;
         }
 
-        let path = &self.path[self.cur..];
-        let ret = match component().easy_parse(path) {
+        match self.parse_state {
+            PathParseState::Finish | PathParseState::PathComponent => {}
+            _ => self.parse_state = PathParseState::PathComponent,
+        }
+
+        let ret = self.parse_part(cur);
+        let end = self.end.min(self.path.len());
+        if ret.is_err() {
+            // Skip the rest of an invalid component in case the iterator is
+            // recovering from errors
+            let sep = self.path[cur..end]
+                .iter()
+                .position(|&b| is_component_separator(b, self.verbatim));
+            self.cur = match sep {
+                Some(i) => cur + i,
+                None => end,
+            };
+        }
+        if self.cur < end {
+            // Add an additional 1 to account for the separator
+            self.cur += 1;
+        } else {
+            // This is the last component
+            self.end = self.cur;
+        }
+        Some(ret)
+    }
+
+    fn parse_component_back(&mut self) -> Option<PathComponent<'path>> {
+        let start = self.cur.max(self.comp_start);
+        if start >= self.end {
+            self.back_state = BackParseState::Root;
+            return self.parse_root_back();
+        }
+
+        // The last component ends just before its separator
+        let end = self.end - 1;
+        let comp_start = match self.path[start..end]
+            .iter()
+            .rposition(|&b| is_component_separator(b, self.verbatim))
+        {
+            Some(i) => start + i + 1,
+
+            // This is the first component that is left
+            None => start,
+        };
+        self.end = comp_start;
+
+        // parse_part() moves cur past the component, so put it back since
+        // the component was reached from the other end
+        let cur = self.cur;
+        let ret = self.parse_part(comp_start);
+        self.cur = cur;
+        Some(ret)
+    }
+
+    fn parse_root_back(&mut self) -> Option<PathComponent<'path>> {
+        self.back_state = BackParseState::Prefix;
+
+        let root_left = match self.parse_state {
+            PathParseState::Start | PathParseState::Prefix => self.has_root,
+            _ => false,
+        };
+        if !root_left {
+            return self.parse_prefix_back();
+        }
+
+        let start = self.comp_start - 1;
+        self.span = start..self.comp_start;
+        root_at(&self.path[start..], self.verbatim).map(|(comp, _)| comp)
+    }
+
+    fn parse_prefix_back(&mut self) -> Option<PathComponent<'path>> {
+        // Nothing else is left once the prefix is reached
+        let prefix_left = self.parse_state == PathParseState::Start;
+        self.parse_state = PathParseState::Finish;
+
+        if !prefix_left {
+            return None;
+        }
+        let (prefix, _) = prefix_at(self.path)?;
+        self.span = 0..prefix.as_os_str().len();
+        Some(Ok(Component::Prefix(prefix)))
+    }
+
+    // Parses the component that starts at start and moves cur to its end.
+    // Both ends of the iterator use this so that they report the same
+    // errors.
+    fn parse_part(&mut self, start: usize) -> PathComponent<'path> {
+        if let Some(colon) = self.stream_colon(start) {
+            return self.parse_stream_part(start, colon);
+        }
+
+        let path = &self.path[start..];
+        match component_at(path, self.verbatim) {
             Ok(((comp, len), _)) => {
-                // Add an additional 1 to account for the separator
-                let inc = if cur + len < end { len + 1 } else { len };
-                self.cur += inc;
-                Some(comp)
+                self.cur = start + len;
+                self.span = start..self.cur;
+                comp
             }
             Err(err) => {
-                self.parse_state = PathParseState::Finish;
-                Some(Err(self.make_error(self.cur, err)))
+                self.stop_at_error();
+                Err(self.make_error(start, err))
             }
-        };
+        }
+    }
 
-        match self.parse_state {
-            PathParseState::Finish | PathParseState::PathComponent => {}
-            _ => self.parse_state = PathParseState::PathComponent,
+    // Parses the last component, checking the stream after colon and the
+    // base name before it on its own
+    fn parse_stream_part(
+        &mut self,
+        start: usize,
+        colon: usize,
+    ) -> PathComponent<'path> {
+        let end = self.path.len();
+        if !is_valid_stream(&self.path[colon + 1..]) {
+            self.stop_at_error();
+            let msg = "path component has an invalid stream";
+            return Err(self.stream_error(colon, end, msg));
         }
 
-        ret
+        let base = &self.path[start..colon];
+        match component_at(base, self.verbatim) {
+            Ok(((Ok(Component::Normal(_)), len), _))
+                if start + len == colon =>
+            {
+                self.cur = end;
+                self.span = start..end;
+                Ok(Component::Normal(as_osstr(&self.path[start..])))
+            }
+            Ok(((Err(err), _), _)) => {
+                self.stop_at_error();
+                Err(err)
+            }
+            Err(err) => {
+                self.stop_at_error();
+                Err(self.make_error(start, err))
+            }
+            Ok(_) => {
+                self.stop_at_error();
+                let msg = "path component has an invalid base name";
+                Err(self.stream_error(start, end, msg))
+            }
+        }
+    }
+
+    fn stream_error(
+        &self,
+        start: usize,
+        end: usize,
+        msg: &str,
+    ) -> error::ParseError {
+        error::ParseError::new(
+            WindowsErrorKind::InvalidCharacter.into(),
+            as_osstr(&self.path[start..end]).into(),
+            as_osstr(self.path).into(),
+            start,
+            end,
+            String::from(msg),
+        )
     }
 
     fn make_error<I, R>(
@@ -140,7 +445,7 @@ impl<'path> Iter<'path> {
         start: usize,
         parse_error: Errors<I, R, PointerOffset>,
     ) -> error::ParseError {
-        let path = &self.path[..];
+        let path = self.path;
         let path_comp = &path[start..];
 
         let err = parse_error.map_position(|p| p.translate_position(path_comp));
@@ -187,7 +492,7 @@ impl<'path> Iter<'path> {
 
         error::ParseError::new(
             kind,
-            as_osstr(&rest[..]).into(),
+            as_osstr(rest).into(),
             as_osstr(path).into(),
             start,
             end,
@@ -195,6 +500,27 @@ impl<'path> Iter<'path> {
         )
     }
 
+    #[cfg(not(feature = "manual-iter"))]
+    pub(crate) fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    // Where the components that follow the prefix and root begin
+    #[cfg(not(feature = "manual-iter"))]
+    pub(crate) fn components_start(&self) -> usize {
+        self.comp_start
+    }
+
+    #[cfg(not(feature = "manual-iter"))]
+    pub(crate) fn has_root(&self) -> bool {
+        self.has_root
+    }
+
+    #[cfg(not(feature = "manual-iter"))]
+    pub(crate) fn is_verbatim(&self) -> bool {
+        self.verbatim
+    }
+
     #[allow(dead_code)]
     #[cfg(test)]
     pub fn current_index(&self) -> usize {
@@ -217,6 +543,23 @@ impl<'path> Iterator for Iter<'path> {
     }
 }
 
+impl<'path> DoubleEndedIterator for Iter<'path> {
+    fn next_back(&mut self) -> Option<PathComponent<'path>> {
+        if self.parse_state == PathParseState::Finish {
+            return None;
+        }
+        if let Some(err) = self.parse_invalid_host() {
+            return Some(err);
+        }
+
+        match self.back_state {
+            BackParseState::PathComponent => self.parse_component_back(),
+            BackParseState::Root => self.parse_root_back(),
+            BackParseState::Prefix => self.parse_prefix_back(),
+        }
+    }
+}
+
 impl<'path> AsRef<SystemStr> for Iter<'path> {
     fn as_ref(&self) -> &SystemStr {
         SystemStr::from_bytes(&self.path[self.cur..])
@@ -14,6 +14,7 @@ use std::path::Prefix;
 // Third-party imports
 
 // Local imports
+use super::prefix::match_object_prefix;
 use super::{path_type, SEPARATOR};
 use crate::common::string::{as_osstr, as_str, ascii_uppercase};
 
@@ -21,7 +22,11 @@ use crate::common::string::{as_osstr, as_str, ascii_uppercase};
 // Matcher functions
 // ===========================================================================
 
-pub fn match_prefix(path: &[u8]) -> Option<(usize, Prefix)> {
+pub fn match_prefix(path: &[u8]) -> Option<(usize, Prefix<'_>)> {
+    if let Some(found) = match_object_prefix(path) {
+        return Some(found);
+    }
+
     let end = 2;
     if path.len() < end {
         return None;
@@ -36,7 +41,7 @@ pub fn match_prefix(path: &[u8]) -> Option<(usize, Prefix)> {
 }
 
 // Endpoint (from prefix)
-fn match_disk(path: &[u8], first: usize) -> Option<(usize, Prefix)> {
+fn match_disk(path: &[u8], first: usize) -> Option<(usize, Prefix<'_>)> {
     let part = &path[..first];
 
     if part == path_type::Disk {
@@ -48,7 +53,7 @@ fn match_disk(path: &[u8], first: usize) -> Option<(usize, Prefix)> {
 }
 
 // from prefix
-fn match_doubleslash(path: &[u8], first: usize) -> Option<(usize, Prefix)> {
+fn match_doubleslash(path: &[u8], first: usize) -> Option<(usize, Prefix<'_>)> {
     let end = first + 2;
     if path.len() < end {
         return None;
@@ -75,7 +80,7 @@ fn match_doubleslash(path: &[u8], first: usize) -> Option<(usize, Prefix)> {
     }
 }
 
-fn match_verbatim(path: &[u8], first: usize) -> Option<(usize, Prefix)> {
+fn match_verbatim(path: &[u8], first: usize) -> Option<(usize, Prefix<'_>)> {
     let mut end = path.len();
 
     for (i, c) in path[first..end].iter().enumerate() {
@@ -95,7 +100,10 @@ fn match_verbatim(path: &[u8], first: usize) -> Option<(usize, Prefix)> {
     }
 }
 
-fn match_verbatimdisk(path: &[u8], first: usize) -> Option<(usize, Prefix)> {
+fn match_verbatimdisk(
+    path: &[u8],
+    first: usize,
+) -> Option<(usize, Prefix<'_>)> {
     let end = first + 3;
     if path.len() < end {
         return None;
@@ -111,7 +119,7 @@ fn match_verbatimdisk(path: &[u8], first: usize) -> Option<(usize, Prefix)> {
 }
 
 // endpoint (from match_doubleslash)
-fn match_unc(path: &[u8], first: usize) -> Option<(usize, Prefix)> {
+fn match_unc(path: &[u8], first: usize) -> Option<(usize, Prefix<'_>)> {
     let end = path.len();
 
     let mut sep_index: Vec<usize> = Vec::with_capacity(2);
@@ -145,7 +153,7 @@ fn match_unc(path: &[u8], first: usize) -> Option<(usize, Prefix)> {
 }
 
 // endpoint (from match_doubleslash)
-fn match_verbatimunc(path: &[u8], first: usize) -> Option<(usize, Prefix)> {
+fn match_verbatimunc(path: &[u8], first: usize) -> Option<(usize, Prefix<'_>)> {
     let part_end = first + 4;
     if path.len() < part_end {
         return None;
@@ -166,7 +174,7 @@ fn match_verbatimunc(path: &[u8], first: usize) -> Option<(usize, Prefix)> {
 }
 
 // Endpoint (from match_doubleslash)
-fn match_devicens(path: &[u8], first: usize) -> Option<(usize, Prefix)> {
+fn match_devicens(path: &[u8], first: usize) -> Option<(usize, Prefix<'_>)> {
     let mut end = path.len();
 
     // Get all bytes until first separator
@@ -205,6 +213,11 @@ mod test {
 
     // Exclude restricted printable chars and any char with ascii code 0 - 31
     const COMP_REGEX: &str = r#"[^/\\<>:"|?*\x00-\x1F]+"#;
+
+    // Host names made of letters, digits, - and _, which are valid as the
+    // server of a UNC path unless every label is a number
+    const HOST_REGEX: &str =
+        r#"[a-z0-9_]{1,15}(-[a-z0-9_]{1,15}){0,2}(\.[a-z0-9_]{1,15}){0,3}"#;
     const VALID_CHARS: &str =
         r#"[^/\\<>:"|?*\x00-\x1F]*[^./\\<>:"|?*\x00-\x1F ]+"#;
     const VALID_CHARS_NOEXT: &str =
@@ -331,7 +344,7 @@ mod test {
                 // --------------------
                 // the match_prefix() function is called with the Verbatim
                 // path
-                let value = match_prefix(&path[..].as_bytes());
+                let value = match_prefix(path[..].as_bytes());
 
                 // --------------------
                 // THEN
@@ -345,7 +358,7 @@ mod test {
                 let result = match value {
                     Some((end, p)) => {
                         let path_comp = OsStr::new(&comp[0][..]);
-                        &path[..end] == &expected_path[..]
+                        path[..end] == expected_path[..]
                             && p == Prefix::Verbatim(path_comp)
                     }
                     _ => false,
@@ -402,7 +415,7 @@ mod test {
         proptest! {
             #[test]
             fn return_only_server_share(
-                server in VALID_CHARS_NOEXT,
+                server in HOST_REGEX,
                 share in VALID_CHARS_NOEXT,
                 comp in prop::collection::vec(VALID_CHARS, 0..10),
                 sep in prop_oneof!(Just("/"), Just(r#"\"#))
@@ -436,7 +449,7 @@ mod test {
                     share = share
                 );
                 let mut path = prefix.clone();
-                if comp.len() > 0 {
+                if !comp.is_empty() {
                     path.push_str(&sep[..]);
                     path.push_str(&comp.join(&sep)[..]);
                 }
@@ -448,7 +461,7 @@ mod test {
                 // --------------------
                 // the match_prefix() function is called with the Verbatim
                 // path
-                let value = match_prefix(&path[..].as_bytes());
+                let value = match_prefix(path[..].as_bytes());
 
                 // --------------------
                 // THEN
@@ -467,7 +480,7 @@ mod test {
                             comp_server,
                             comp_share
                         );
-                        &path[..end] == &expected_path[..]
+                        path[..end] == expected_path[..]
                             && p == expected
                     }
                     _ => false,
@@ -544,7 +557,7 @@ mod test {
                     disk = drive
                 );
                 let mut path = prefix.clone();
-                if comp.len() > 0 {
+                if !comp.is_empty() {
                     path.push_str(&comp.join(&sep)[..]);
                 }
 
@@ -555,7 +568,7 @@ mod test {
                 // --------------------
                 // the match_prefix() function is called with the Verbatim
                 // path
-                let value = match_prefix(&path[..].as_bytes());
+                let value = match_prefix(path[..].as_bytes());
 
                 // --------------------
                 // THEN
@@ -570,7 +583,7 @@ mod test {
                     Some((end, p)) => {
                         let comp_disk = disk_upper.as_bytes()[0];
                         let expected = Prefix::VerbatimDisk(comp_disk);
-                        &path[..end] == expected_path
+                        path[..end] == expected_path
                             && p == expected
                     }
                     _ => false,
@@ -578,7 +591,6 @@ mod test {
                 prop_assert!(result);
             }
         }
-
     }
 
     mod devicens {
@@ -666,7 +678,7 @@ mod test {
                     device = device
                 );
                 let mut path = prefix.clone();
-                if comp.len() > 0 {
+                if !comp.is_empty() {
                     path.push_str(&sep[..]);
                     path.push_str(&comp.join(&sep)[..]);
                 }
@@ -678,7 +690,7 @@ mod test {
                 // --------------------
                 // the match_prefix() function is called with the DeviceNS
                 // path
-                let value = match_prefix(&path[..].as_bytes());
+                let value = match_prefix(path[..].as_bytes());
 
                 // --------------------
                 // THEN
@@ -693,7 +705,7 @@ mod test {
                     Some((end, p)) => {
                         let comp_device = OsStr::new(device.as_str());
                         let expected = Prefix::DeviceNS(comp_device);
-                        &path[..end] == expected_path
+                        path[..end] == expected_path
                             && p == expected
                     }
                     _ => false,
@@ -750,7 +762,7 @@ mod test {
         proptest! {
             #[test]
             fn return_only_server_share(
-                server in VALID_CHARS,
+                server in HOST_REGEX,
                 share in VALID_CHARS,
                 comp in prop::collection::vec(VALID_CHARS, 0..10),
                 sep in prop_oneof!(Just("/"), Just(r#"\"#))
@@ -784,7 +796,7 @@ mod test {
                     share = share
                 );
                 let mut path = prefix.clone();
-                if comp.len() > 0 {
+                if !comp.is_empty() {
                     path.push_str(&sep[..]);
                     path.push_str(&comp.join(&sep)[..]);
                 }
@@ -796,7 +808,7 @@ mod test {
                 // --------------------
                 // the match_prefix() function is called with the Verbatim
                 // path
-                let value = match_prefix(&path[..].as_bytes());
+                let value = match_prefix(path[..].as_bytes());
 
                 // --------------------
                 // THEN
@@ -815,7 +827,7 @@ mod test {
                             comp_server,
                             comp_share
                         );
-                        &path[..end] == &expected_path[..]
+                        path[..end] == expected_path[..]
                             && p == expected
                     }
                     _ => false,
@@ -895,7 +907,7 @@ mod test {
                     path.push_str(&sep[..]);
                 }
 
-                if comp.len() > 0 {
+                if !comp.is_empty() {
                     path.push_str(&comp.join(&sep)[..]);
                 }
 
@@ -906,7 +918,7 @@ mod test {
                 // --------------------
                 // the match_prefix() function is called with the Disk
                 // path
-                let value = match_prefix(&path[..].as_bytes());
+                let value = match_prefix(path[..].as_bytes());
 
                 // --------------------
                 // THEN
@@ -921,7 +933,7 @@ mod test {
                     Some((end, p)) => {
                         let comp_disk = disk_upper.as_bytes()[0];
                         let expected = Prefix::Disk(comp_disk);
-                        &path[..end] == expected_path
+                        path[..end] == expected_path
                             && p == expected
                     }
                     _ => false,
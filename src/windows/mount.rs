@@ -0,0 +1,476 @@
+// src/windows/mount.rs
+// Copyright (C) 2019 authors and contributors (see AUTHORS file)
+//
+// This file is released under the MIT License.
+
+// ===========================================================================
+// Imports
+// ===========================================================================
+
+// Stdlib imports
+use std::ffi::{OsStr, OsString};
+
+// Third-party imports
+
+// Local imports
+use super::convert::{same_prefix, split_root};
+use super::{Component, Iter, PrefixComponent, PrefixKind};
+use crate::common::string::ascii_uppercase;
+use crate::path::{PathIterator, SystemSeq, SystemStr};
+use crate::unix::{self, UnixPath};
+
+// ===========================================================================
+// MountTable
+// ===========================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Mount {
+    // Each drive at a directory under dir named after its lowercase letter,
+    // followed by : if colon is set
+    Drives { dir: OsString, colon: bool },
+
+    // A Windows directory at a Unix one
+    Path { unix: OsString, windows: OsString },
+}
+
+// Where Windows roots and directories are found in the Unix filesystem, for
+// use with RootMap::Mounts. A path is converted with the mount that covers
+// the most of its components, and ties go to the mount added first. A path
+// that climbs out of its mount with .., eg /mnt/c/../d, is not mapped, since
+// .. would then name a different directory on each system.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MountTable {
+    mounts: Vec<Mount>,
+}
+
+impl MountTable {
+    pub fn new() -> MountTable {
+        MountTable { mounts: Vec::new() }
+    }
+
+    // Mounts each drive at a directory under dir, eg /mnt/c for C:\ when
+    // dir is /mnt
+    pub fn with_drives<P: AsRef<OsStr> + ?Sized>(
+        mut self,
+        dir: &P,
+    ) -> MountTable {
+        self.mounts.push(Mount::Drives {
+            dir: dir.as_ref().to_os_string(),
+            colon: false,
+        });
+        self
+    }
+
+    // Like with_drives(), but the directories are named like the entries of
+    // Wine's dosdevices, eg dosdevices/c: for C:\
+    pub fn with_dos_devices<P: AsRef<OsStr> + ?Sized>(
+        mut self,
+        dir: &P,
+    ) -> MountTable {
+        self.mounts.push(Mount::Drives {
+            dir: dir.as_ref().to_os_string(),
+            colon: true,
+        });
+        self
+    }
+
+    // Mounts the absolute Windows path windows at the absolute Unix path
+    // unix, eg C:\msys64 at /. The Windows path may use / as its
+    // separator, but a mount whose paths hold .. is never used.
+    pub fn with_mount<U, W>(mut self, unix: &U, windows: &W) -> MountTable
+    where
+        U: AsRef<OsStr> + ?Sized,
+        W: AsRef<OsStr> + ?Sized,
+    {
+        self.mounts.push(Mount::Path {
+            unix: unix.as_ref().to_os_string(),
+            windows: windows.as_ref().to_os_string(),
+        });
+        self
+    }
+
+    // WSL, seen from inside the distribution distro: drives are under /mnt,
+    // and Windows reaches / through \\wsl$\distro. The \\wsl.localhost
+    // server that newer versions of Windows use is also mapped to /, but
+    // Unix paths are converted to \\wsl$.
+    pub fn wsl<S: AsRef<OsStr> + ?Sized>(distro: &S) -> MountTable {
+        let share = |server: &str| {
+            let mut share = OsString::from(server);
+            share.push(distro);
+            share
+        };
+        MountTable::new()
+            .with_drives("/mnt")
+            .with_mount("/", &share(r"\\wsl$\"))
+            .with_mount("/", &share(r"\\wsl.localhost\"))
+    }
+
+    // Cygwin, with drives under /cygdrive
+    pub fn cygwin() -> MountTable {
+        MountTable::new().with_drives("/cygdrive")
+    }
+
+    // MSYS2, with drives directly under /, eg /c for C:\
+    pub fn msys2() -> MountTable {
+        MountTable::new().with_drives("/")
+    }
+
+    // Wine, with the prefix at prefix (usually ~/.wine, given with the home
+    // directory expanded). C: is the drive_c directory of the prefix, Z: is
+    // /, and any other drive is an entry of the dosdevices directory.
+    pub fn wine<P: AsRef<OsStr> + ?Sized>(prefix: &P) -> MountTable {
+        let prefix = UnixPath::new(prefix);
+        MountTable::new()
+            .with_mount(prefix.join("drive_c").as_os_str(), r"C:\")
+            .with_mount("/", r"Z:\")
+            .with_dos_devices(prefix.join("dosdevices").as_os_str())
+    }
+}
+
+// ===========================================================================
+// Matching
+// ===========================================================================
+
+type Root = Option<(usize, Vec<u8>)>;
+
+impl MountTable {
+    // The Windows root for the components that follow / in a Unix path,
+    // and how many of those components it stands for
+    pub(crate) fn windows_root(&self, comps: &[unix::Component]) -> Root {
+        let found = self.mounts.iter().fold(None, |best, mount| {
+            longest(best, mount.windows_root(comps))
+        })?;
+        let step = |comp: &unix::Component| match comp {
+            unix::Component::Normal(_) => 1,
+            unix::Component::ParentDir => -1,
+            _ => 0,
+        };
+        if climbs_out(comps[found.0..].iter().map(step)) {
+            None
+        } else {
+            Some(found)
+        }
+    }
+
+    // The Unix root for the prefix and root of a Windows path, and how many
+    // components of the path it stands for
+    pub(crate) fn unix_root(&self, comps: &[Component]) -> Root {
+        let found = self
+            .mounts
+            .iter()
+            .fold(None, |best, mount| longest(best, mount.unix_root(comps)))?;
+        let step = |comp: &Component| match comp {
+            Component::Normal(_) => 1,
+            Component::ParentDir => -1,
+            _ => 0,
+        };
+        if climbs_out(comps[found.0..].iter().map(step)) {
+            None
+        } else {
+            Some(found)
+        }
+    }
+}
+
+impl Mount {
+    fn windows_root(&self, comps: &[unix::Component]) -> Root {
+        match self {
+            Mount::Drives { dir, colon } => unix_drive_len(dir, *colon, comps),
+            Mount::Path { unix, windows } => {
+                let len = unix_dir_len(unix, comps)?;
+                Some((len, windows_dir(windows)?))
+            }
+        }
+    }
+
+    fn unix_root(&self, comps: &[Component]) -> Root {
+        match self {
+            Mount::Drives { dir, colon } => {
+                unix_names(dir)?;
+                match split_root(comps)? {
+                    (PrefixKind::Disk(letter), len)
+                    | (PrefixKind::VerbatimDisk(letter), len) => {
+                        Some((len, drive_dir(dir, letter, *colon)))
+                    }
+                    _ => None,
+                }
+            }
+            Mount::Path { unix, windows } => {
+                unix_names(unix)?;
+                let len = windows_dir_len(windows, comps)?;
+                Some((len, SystemStr::new(unix).as_bytes().to_vec()))
+            }
+        }
+    }
+}
+
+// Whether a path leaves the directory it starts in, given how many levels
+// each of its components goes down
+fn climbs_out<I: Iterator<Item = isize>>(mut steps: I) -> bool {
+    let mut depth = 0;
+    steps.any(|step| {
+        depth += step;
+        depth < 0
+    })
+}
+
+fn longest(best: Root, found: Root) -> Root {
+    match (&best, &found) {
+        (Some((best_len, _)), Some((len, _))) if len <= best_len => best,
+        (_, None) => best,
+        _ => found,
+    }
+}
+
+// The names after / of an absolute Unix path
+fn unix_names(dir: &OsStr) -> Option<Vec<&OsStr>> {
+    let mut iter = unix::Iter::new(SystemStr::new(dir));
+    if iter.next()? != Ok(unix::Component::RootDir) {
+        return None;
+    }
+    iter.filter(|comp| *comp != Ok(unix::Component::CurDir))
+        .map(|comp| match comp {
+            Ok(unix::Component::Normal(name)) => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+// How many of comps are the names of dir
+fn unix_dir_len(dir: &OsStr, comps: &[unix::Component]) -> Option<usize> {
+    let names = unix_names(dir)?;
+    let is_match = comps.len() >= names.len()
+        && names
+            .iter()
+            .zip(comps.iter())
+            .all(|(name, comp)| *comp == unix::Component::Normal(name));
+    if is_match {
+        Some(names.len())
+    } else {
+        None
+    }
+}
+
+fn unix_drive_len(dir: &OsStr, colon: bool, comps: &[unix::Component]) -> Root {
+    let len = unix_dir_len(dir, comps)?;
+    let name = match comps.get(len)? {
+        unix::Component::Normal(name) => SystemStr::new(name).as_bytes(),
+        _ => return None,
+    };
+    let letter = match (name, colon) {
+        ([letter], false) | ([letter, b':'], true) => *letter,
+        _ => return None,
+    };
+
+    if letter.is_ascii_alphabetic() {
+        Some((len + 1, vec![ascii_uppercase(letter), b':', b'\\']))
+    } else {
+        None
+    }
+}
+
+// The directory that a drive is mounted at under dir
+fn drive_dir(dir: &OsStr, letter: u8, colon: bool) -> Vec<u8> {
+    let dir = SystemStr::new(dir).as_bytes();
+    let end = dir.iter().rposition(|&b| b != b'/').map_or(0, |i| i + 1);
+    let mut ret = dir[..end].to_vec();
+    ret.push(b'/');
+    ret.push(letter.to_ascii_lowercase());
+    if colon {
+        ret.push(b':');
+    }
+    ret
+}
+
+// The prefix and the names after the root of an absolute Windows path
+fn windows_names(dir: &OsStr) -> Option<(PrefixComponent<'_>, Vec<&OsStr>)> {
+    let comps = Iter::new(SystemStr::new(dir))
+        .filter(|comp| *comp != Ok(Component::CurDir))
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+    let (_, len) = split_root(&comps)?;
+    let prefix = match comps[0] {
+        Component::Prefix(prefix) => prefix,
+        _ => return None,
+    };
+    let names = comps[len..]
+        .iter()
+        .map(|comp| match comp {
+            Component::Normal(name) => Some(*name),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some((prefix, names))
+}
+
+// The absolute Windows path dir, rebuilt with \ as its separator
+fn windows_dir(dir: &OsStr) -> Option<Vec<u8>> {
+    let (prefix, names) = windows_names(dir)?;
+    let mut ret = SystemStr::new(prefix.as_os_str()).as_bytes().to_vec();
+    if !prefix.kind().is_verbatim() {
+        for b in ret.iter_mut().filter(|b| **b == b'/') {
+            *b = b'\\';
+        }
+    }
+
+    if !ret.ends_with(b"\\") {
+        ret.push(b'\\');
+    }
+    for (i, name) in names.iter().enumerate() {
+        if i > 0 {
+            ret.push(b'\\');
+        }
+        ret.extend_from_slice(SystemStr::new(name).as_bytes());
+    }
+    Some(ret)
+}
+
+// How many of comps, which start with the prefix and root of an absolute
+// path, are covered by dir. Names are compared case insensitively.
+fn windows_dir_len(dir: &OsStr, comps: &[Component]) -> Option<usize> {
+    let (dir_prefix, names) = windows_names(dir)?;
+    let (kind, len) = split_root(comps)?;
+    let rest = &comps[len..];
+
+    let same_name = |a: &OsStr, b: &Component| match b {
+        Component::Normal(b) => SystemStr::new(a)
+            .as_bytes()
+            .eq_ignore_ascii_case(SystemStr::new(b).as_bytes()),
+        _ => false,
+    };
+    let is_match = same_prefix(dir_prefix.classify(), kind)
+        && rest.len() >= names.len()
+        && names.iter().zip(rest.iter()).all(|(a, b)| same_name(a, b));
+    if is_match {
+        Some(len + names.len())
+    } else {
+        None
+    }
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::error::ConvertError;
+    use crate::unix::UnixPathBuf;
+    use crate::windows::{ConvertPolicy, RootMap, WindowsPath, WindowsPathBuf};
+
+    fn policy(table: MountTable) -> ConvertPolicy {
+        ConvertPolicy::new().with_root(RootMap::Mounts(table))
+    }
+
+    // Checks that unix and windows convert to each other
+    fn assert_mapped(policy: &ConvertPolicy, unix: &str, windows: &str) {
+        let converted = UnixPath::new(unix).to_windows(policy);
+        assert_eq!(converted, Ok(WindowsPathBuf::from(windows)), "{}", unix);
+        let converted = WindowsPath::new(windows).to_unix(policy);
+        assert_eq!(converted, Ok(UnixPathBuf::from(unix)), "{}", windows);
+    }
+
+    #[test]
+    fn wsl() {
+        let wsl = policy(MountTable::wsl("Ubuntu"));
+        assert_mapped(&wsl, "/mnt/c/Users/me", r"C:\Users\me");
+        assert_mapped(&wsl, "/mnt/d", r"D:\");
+        assert_mapped(&wsl, "/home/me", r"\\wsl$\Ubuntu\home\me");
+        assert_mapped(&wsl, "/mnt", r"\\wsl$\Ubuntu\mnt");
+
+        let converted = WindowsPath::new(r"\\WSL$\ubuntu\etc").to_unix(&wsl);
+        assert_eq!(converted, Ok(UnixPathBuf::from("/etc")));
+        let converted =
+            WindowsPath::new(r"\\wsl.localhost\Ubuntu\etc").to_unix(&wsl);
+        assert_eq!(converted, Ok(UnixPathBuf::from("/etc")));
+        let converted = WindowsPath::new(r"\\?\c:\x").to_unix(&wsl);
+        assert_eq!(converted, Ok(UnixPathBuf::from("/mnt/c/x")));
+
+        let converted = UnixPath::new("//mnt//c/x").to_windows(&wsl);
+        assert_eq!(converted, Ok(WindowsPathBuf::from(r"C:\x")));
+    }
+
+    #[test]
+    fn cygwin_and_msys2() {
+        let cygwin = policy(MountTable::cygwin());
+        assert_mapped(&cygwin, "/cygdrive/c/x", r"C:\x");
+
+        let msys2 = policy(MountTable::msys2().with_mount("/", r"C:\msys64"));
+        assert_mapped(&msys2, "/c/x", r"C:\x");
+        assert_mapped(&msys2, "/usr/bin", r"C:\msys64\usr\bin");
+        assert_mapped(&msys2, "/", r"C:\msys64");
+        assert_mapped(&msys2, "relative/x", r"relative\x");
+
+        // The root is rebuilt with \ no matter how the mount was written
+        let msys2 = policy(MountTable::msys2().with_mount("/", "C:/msys64/"));
+        let converted = UnixPath::new("/usr/bin").to_windows(&msys2);
+        assert_eq!(converted, Ok(WindowsPathBuf::from(r"C:\msys64\usr\bin")));
+        let unc = policy(MountTable::new().with_mount("/", "//srv/share/./x"));
+        let converted = UnixPath::new("/y").to_windows(&unc);
+        assert_eq!(converted, Ok(WindowsPathBuf::from(r"\\srv\share\x\y")));
+
+        // /cc is not a drive, so it is under C:\msys64
+        let converted = UnixPath::new("/cc").to_windows(&msys2);
+        assert_eq!(converted, Ok(WindowsPathBuf::from(r"C:\msys64\cc")));
+    }
+
+    #[test]
+    fn wine() {
+        let wine = policy(MountTable::wine("/home/me/.wine"));
+        assert_mapped(&wine, "/home/me/.wine/drive_c/windows", r"C:\windows");
+        assert_mapped(&wine, "/home/me/.wine/dosdevices/d:/x", r"D:\x");
+        assert_mapped(&wine, "/etc/hosts", r"Z:\etc\hosts");
+
+        let converted =
+            UnixPath::new("/home/me/.wine/dosdevices/c:/x").to_windows(&wine);
+        assert_eq!(converted, Ok(WindowsPathBuf::from(r"C:\x")));
+    }
+
+    #[test]
+    fn parent_dirs() {
+        let wsl = policy(MountTable::wsl("Ubuntu"));
+        assert_mapped(&wsl, "/mnt/c/x/../y", r"C:\x\..\y");
+        assert_mapped(&wsl, "/home/..", r"\\wsl$\Ubuntu\home\..");
+
+        // .. that leaves the mount would name another directory on Windows
+        for path in ["/mnt/c/../d", "/mnt/c/x/../../d", "/.."].iter() {
+            let err = UnixPath::new(path).to_windows(&wsl).unwrap_err();
+            assert_eq!(err, ConvertError::UnmappedRoot, "{}", path);
+        }
+        for path in [r"C:\..\x", r"\\wsl$\Ubuntu\..\x"].iter() {
+            let err = WindowsPath::new(path).to_unix(&wsl).unwrap_err();
+            assert_eq!(err, ConvertError::UnmappedRoot, "{}", path);
+        }
+    }
+
+    #[test]
+    fn unmapped() {
+        let cygwin = policy(MountTable::cygwin());
+        for path in ["/home", "/cygdrive", "/cygdrive/cc", "/cygdrive/1"].iter()
+        {
+            let err = UnixPath::new(path).to_windows(&cygwin).unwrap_err();
+            assert_eq!(err, ConvertError::UnmappedRoot, "{}", path);
+        }
+        for path in [r"\\server\share\x", r"\x", "C:x"].iter() {
+            let err = WindowsPath::new(path).to_unix(&cygwin).unwrap_err();
+            assert_eq!(err, ConvertError::UnmappedRoot, "{}", path);
+        }
+
+        // Mounts that are not absolute never match
+        let table = MountTable::new()
+            .with_drives("mnt")
+            .with_mount("/x", r"C:dir");
+        let converted = UnixPath::new("/x/y").to_windows(&policy(table));
+        assert_eq!(converted, Err(ConvertError::UnmappedRoot));
+
+        // Nor do mounts whose Windows paths hold ..
+        let table = MountTable::new().with_mount("/x", r"C:\a\..\b");
+        let converted = UnixPath::new("/x/y").to_windows(&policy(table));
+        assert_eq!(converted, Err(ConvertError::UnmappedRoot));
+    }
+}
+
+// ===========================================================================
+//
+// ===========================================================================
@@ -22,16 +22,16 @@ use combine::{
         Parser,
     },
     range::take_while,
-    sep_by,
     stream::{FullRangeStream, RangeStream},
-    token, unexpected_any, value,
+    unexpected_any, value,
 };
 use lazy_static::lazy_static;
 use regex::bytes as regex_bytes;
 
 // Local imports
 use super::iter::{Component, PrefixComponent};
-use super::{RESERVED_NAMES, RESTRICTED_CHARS};
+use super::unc::is_valid_host;
+use super::{is_reserved_name, RESERVED_NAMES, RESTRICTED_CHARS};
 use crate::common::error;
 use crate::common::string::{as_osstr, ascii_uppercase};
 
@@ -43,9 +43,13 @@ pub(crate) const RESTRICTED_NAME_ERRMSG: &str = "reserved name used";
 
 lazy_static! {
     static ref DEVICE_REGEX: regex_bytes::Regex = {
-        let regex = RESERVED_NAMES.iter().fold(String::new(), |mut s, name| {
+        // Longer names go first so that eg CONIN$ is not matched as CON
+        let mut names: Vec<&String> = RESERVED_NAMES.iter().collect();
+        names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+
+        let regex = names.iter().fold(String::new(), |mut s, name| {
             if !s.is_empty() {
-                s.push_str("|");
+                s.push('|');
             }
 
             let regex = name.bytes().fold("(?i-u)".to_owned(), |mut s, b| {
@@ -66,7 +70,7 @@ lazy_static! {
         regex_bytes::Regex::new(regex.as_str()).unwrap()
     };
     static ref UNC_WORD: regex_bytes::Regex =
-        { regex_bytes::Regex::new("(?i)UNC").unwrap() };
+        regex_bytes::Regex::new("(?i)UNC").unwrap();
 }
 
 // ===========================================================================
@@ -87,6 +91,25 @@ where
     choice!(attempt(range(&b"\\"[..])), attempt(range(&b"/"[..])))
 }
 
+// After a verbatim prefix only \\ is a separator
+fn verbatim_separator<'a, I>() -> impl Parser<Input = I, Output = &'a [u8]>
+where
+    I: RangeStream<Item = u8, Range = &'a [u8]>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    range(&b"\\"[..])
+}
+
+pub fn verbatim_root<'a, I>(
+) -> impl Parser<Input = I, Output = (PathComponent<'a>, usize)>
+where
+    I: RangeStream<Item = u8, Range = &'a [u8]> + FullRangeStream,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    verbatim_separator()
+        .map(|sep| (Ok(Component::RootDir(as_osstr(sep))), sep.len()))
+}
+
 pub fn root<'a, I>(
 ) -> impl Parser<Input = I, Output = (PathComponent<'a>, usize)>
 where
@@ -179,85 +202,66 @@ where
     take_while(|b: u8| !RESTRICTED_CHARS.contains(&b))
 }
 
-fn file_parts<'a, I>() -> impl Parser<Input = I, Output = Vec<&'a [u8]>>
+fn nondevice_part<'a, I>() -> impl Parser<Input = I, Output = &'a [u8]>
 where
     I: RangeStream<Item = u8, Range = &'a [u8]>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    sep_by(
-        take_while(|b: u8| b != b'.' && !RESTRICTED_CHARS.contains(&b)),
-        token(b'.'),
-    )
+    let sep = choice!(attempt(separator().map(|_| ())), attempt(eof()));
+    let part = valid_part_char().skip(look_ahead(sep));
+
+    part.then(checked_part)
 }
 
-fn file_name<'a, I>(
-) -> impl Parser<Input = I, Output = Option<(Vec<u8>, &'a [u8])>> + 'a
+// A component after a verbatim prefix, where / is an ordinary character.
+// Reserved names and a trailing dot or space are not checked, since Windows
+// passes the component to the file system as it is.
+fn verbatim_part<'a, I>() -> impl Parser<Input = I, Output = &'a [u8]>
 where
-    I: 'a + RangeStream<Item = u8, Range = &'a [u8]>,
+    I: RangeStream<Item = u8, Range = &'a [u8]>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    file_parts().then(|parts| {
-        let parts_len = parts.len();
-        if parts.is_empty() || (parts_len == 1 && parts[0].is_empty()) {
-            value(None)
-        } else if parts_len == 1 {
-            let last = *parts.last().unwrap();
-            value(Some((last.to_vec(), &[][..])))
-        } else {
-            let slice = &parts[..parts.len() - 1];
-            let last = *parts.last().unwrap();
-            let name = slice.join(&b'.');
-            value(Some((name, last)))
-        }
-    })
+    let sep =
+        choice!(attempt(verbatim_separator().map(|_| ())), attempt(eof()));
+    take_while(|b: u8| b == b'/' || !RESTRICTED_CHARS.contains(&b))
+        .skip(look_ahead(sep))
 }
 
-fn nondevice_part<'a, I>() -> impl Parser<Input = I, Output = &'a [u8]>
+// Fails if part has an invalid last character or uses a reserved name
+fn checked_part<'a, I>(
+    part: &'a [u8],
+) -> impl Parser<Input = I, Output = &'a [u8]>
 where
     I: RangeStream<Item = u8, Range = &'a [u8]>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    let sep = choice!(attempt(separator().map(|_| ())), attempt(eof()));
-    let part = valid_part_char().skip(look_ahead(sep));
+    if part.is_empty() {
+        return value(part).left();
+    }
 
-    part.then(|part: &'a [u8]| {
-        if part.is_empty() {
-            return value(part).left();
+    let mut parser = choice!(attempt(parentdir()), attempt(curdir()));
+    let res = parser.easy_parse(part);
+    if res.is_ok() {
+        value(part).left()
+    } else {
+        let last = *part.last().unwrap();
+        match last {
+            b' ' | b'.' => {
+                return unexpected_any(Info::Range(part))
+                    .message("last character is invalid")
+                    .right();
+            }
+            _ => {}
         }
-
-        let mut parser = choice!(attempt(parentdir()), attempt(curdir()));
-        let res = parser.easy_parse(part);
-        if res.is_ok() {
-            value(part).left()
+        // Fail if the file name matches a reserved name
+        if is_reserved_name(part) {
+            unexpected_any(Info::Range(part))
+                .message(RESTRICTED_NAME_ERRMSG)
+                .right()
         } else {
-            let last = *part.last().unwrap();
-            match last {
-                b' ' | b'.' => {
-                    return unexpected_any(Info::Range(part))
-                        .message("last character is invalid")
-                        .right();
-                }
-                _ => {}
-            }
-            let ret = value(part).left();
-
-            // This should always succeed since it has already been successfully
-            // parsed
-            let mut parser = file_name();
-            let file_name = parser.easy_parse(part).unwrap();
-            let file_name = file_name.0.unwrap();
-
-            // Fail if the file name matches a reserved name
-            let mut parser = device();
-            let file_device = parser.parse(&file_name.0[..]);
-            match file_device {
-                Ok(_) => unexpected_any(Info::Range(part))
-                    .message(RESTRICTED_NAME_ERRMSG)
-                    .right(),
-                Err(_) => ret,
-            }
+            value(part).left()
         }
-    })
+    }
 }
 
 fn nonunc_part<'a, I>() -> impl Parser<Input = I, Output = &'a [u8]>
@@ -278,7 +282,16 @@ where
     I: RangeStream<Item = u8, Range = &'a [u8]>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    nondevice_part().skip(separator()).and(nondevice_part())
+    let server = nondevice_part().then(|server: &'a [u8]| {
+        if is_valid_host(server) {
+            value(server).left()
+        } else {
+            unexpected_any(Info::Range(server))
+                .message("invalid host name")
+                .right()
+        }
+    });
+    server.skip(separator()).and(nondevice_part())
 }
 
 fn verbatim_start<'a, I>() -> impl Parser<Input = I, Output = &'a [u8]>
@@ -453,6 +466,25 @@ where
     })
 }
 
+// A component after a verbatim prefix. Windows does no normalization of
+// these, so . and .. are ordinary names.
+pub fn verbatim_component<'a, I>(
+) -> impl Parser<Input = I, Output = (PathComponent<'a>, usize)>
+where
+    I: RangeStream<Item = u8, Range = &'a [u8]> + FullRangeStream,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    let sep =
+        choice!(attempt(verbatim_separator().map(|_| ())), attempt(eof()));
+    verbatim_part().skip(sep).map(|comp| {
+        if comp.is_empty() {
+            (Ok(Component::CurDir), 0)
+        } else {
+            (Ok(Component::Normal(as_osstr(comp))), comp.len())
+        }
+    })
+}
+
 // ===========================================================================
 // Tests
 // ===========================================================================
@@ -462,22 +494,6 @@ where
 mod test {
     use combine::Parser;
 
-    mod file_name {
-        use super::*;
-        use crate::windows::parser::file_name;
-
-        #[test]
-        fn empty_filename() {
-            let name = b"";
-            let parse_result = file_name().parse(&name[..]);
-            let result = match parse_result {
-                Err(_) => false,
-                Ok((cur, _)) => cur.is_none(),
-            };
-            assert!(result);
-        }
-    }
-
     mod prefix_verbatimunc {
         use super::*;
         use crate::windows::parser::prefix_verbatimunc;
@@ -486,10 +502,7 @@ mod test {
         fn simple_parse() {
             let path = b"//?/UNC/server/share";
             let parse_result = prefix_verbatimunc().parse(&path[..]);
-            let result = match parse_result {
-                Err(_) => false,
-                Ok(_) => true,
-            };
+            let result = parse_result.is_ok();
             assert!(result);
         }
     }
@@ -511,7 +524,7 @@ mod test {
                     let eof = rest.is_empty();
                     let prefix_kind = Prefix::DeviceNS(OsStr::new("COM4"));
                     let prefix_comp =
-                        PrefixComponent::new(&path[..], prefix_kind.clone());
+                        PrefixComponent::new(&path[..], prefix_kind);
                     let (comp, len) = cur;
                     let expected_len = len == path.len();
                     match comp.unwrap() {
@@ -545,7 +558,7 @@ mod test {
                     let prefix_kind =
                         Prefix::UNC(OsStr::new("server"), OsStr::new("share"));
                     let prefix_comp =
-                        PrefixComponent::new(&path[..], prefix_kind.clone());
+                        PrefixComponent::new(&path[..], prefix_kind);
                     let (comp, len) = cur;
                     match comp.unwrap() {
                         Component::Prefix(c) => {
@@ -561,6 +574,31 @@ mod test {
         }
     }
 
+    mod verbatim_component {
+        use super::*;
+        use crate::windows::iter::Component;
+        use crate::windows::parser::verbatim_component;
+        use std::ffi::OsStr;
+
+        #[test]
+        fn reserved_and_trailing_names() {
+            let names = ["CON", "foo.", "a.", "..", "nul.txt ", "a/b"];
+            for name in names.iter() {
+                let path = format!(r"{}\rest", name);
+                let parse_result = verbatim_component().parse(path.as_bytes());
+                let ((comp, len), _) = parse_result.unwrap();
+                assert_eq!(comp.unwrap(), Component::Normal(OsStr::new(name)));
+                assert_eq!(len, name.len());
+            }
+        }
+
+        #[test]
+        fn restricted_char() {
+            let parse_result = verbatim_component().parse(&b"a<b"[..]);
+            assert!(parse_result.is_err());
+        }
+    }
+
     mod prefix {
         use super::*;
         use crate::windows::parser::prefix;
@@ -581,7 +619,7 @@ mod test {
             {
                 let path_str = path.to_owned();
                 let path = path_str.as_bytes();
-                let parse_result = prefix().parse(&path[..]);
+                let parse_result = prefix().parse(path);
                 assert!(parse_result.is_ok());
             }
         }
@@ -25,20 +25,24 @@ use std::fmt;
 use lazy_static::lazy_static;
 
 // Local imports
-use super::{DRIVE_LETTERS, RESERVED_NAMES, RESTRICTED_CHARS, SEPARATOR};
+use super::unc::is_valid_host;
+use super::{is_reserved_name, DRIVE_LETTERS, RESTRICTED_CHARS, SEPARATOR};
 use crate::common::path_type::{CurrentDir, ParentDir};
 use crate::mk_reverse_equal;
 
+#[cfg(test)]
+use super::RESERVED_NAMES;
+
 // ===========================================================================
 // Globals
 // ===========================================================================
 
 lazy_static! {
-    static ref UNC_LETTERS: HashSet<u8> =
-        { b"uncUNC".iter().cloned().collect() };
-    static ref UNC_WORD_BYTES: Vec<u8> = { b"UNC".to_vec() };
-    static ref INVALID_LAST_CHAR: HashSet<u8> =
-        { b" .".iter().cloned().collect() };
+    static ref UNC_LETTERS: HashSet<u8> = b"uncUNC".iter().cloned().collect();
+    static ref UNC_WORD_BYTES: Vec<u8> = b"UNC".to_vec();
+    static ref INVALID_LAST_CHAR: HashSet<u8> = b" .".iter().cloned().collect();
+    static ref SHORT_NAME_SYMBOLS: HashSet<u8> =
+        b"!#$%&'()-@^_`{}~".iter().cloned().collect();
 }
 
 // ===========================================================================
@@ -137,9 +141,7 @@ macro_rules! slash_type {
                     return false;
                 }
 
-                if other[0] != $first_char as u8
-                    || !SEPARATOR.contains(&other[1])
-                {
+                if other[0] != $first_char || !SEPARATOR.contains(&other[1]) {
                     return false;
                 }
 
@@ -151,9 +153,9 @@ macro_rules! slash_type {
     };
 }
 
-slash_type!(QuestionSlash, '?');
+slash_type!(QuestionSlash, b'?');
 
-slash_type!(DotSlash, '.');
+slash_type!(DotSlash, b'.');
 
 // ===========================================================================
 // Device
@@ -164,29 +166,7 @@ pub struct Device;
 
 impl PartialEq<&[u8]> for Device {
     fn eq(&self, other: &&[u8]) -> bool {
-        let ext_start = {
-            let mut index = 0;
-            for (i, byte) in other.iter().enumerate() {
-                if *byte == b'.' {
-                    index = i;
-                }
-            }
-
-            index
-        };
-
-        let bytes = {
-            if ext_start == 0 {
-                other.to_vec()
-            } else {
-                other[..ext_start].to_vec()
-            }
-        };
-
-        match String::from_utf8(bytes) {
-            Err(_) => false,
-            Ok(s) => RESERVED_NAMES.contains(&s.to_uppercase()),
-        }
+        is_reserved_name(other)
     }
 }
 
@@ -309,6 +289,26 @@ impl PartialEq<&[u8]> for NonDevicePart {
 
 mk_reverse_equal!(NonDevicePart, &[u8]);
 
+// ===========================================================================
+// VerbatimPart
+// ===========================================================================
+
+// A component after a verbatim prefix. Windows passes these to the file
+// system as they are, so reserved names and a trailing dot or space are
+// allowed; only the restricted characters other than / are not.
+#[derive(Debug)]
+pub struct VerbatimPart;
+
+impl PartialEq<&[u8]> for VerbatimPart {
+    fn eq(&self, other: &&[u8]) -> bool {
+        !other
+            .iter()
+            .any(|&b| b != b'/' && RESTRICTED_CHARS.contains(&b))
+    }
+}
+
+mk_reverse_equal!(VerbatimPart, &[u8]);
+
 // ===========================================================================
 // ServerShare
 // ===========================================================================
@@ -323,6 +323,11 @@ impl PartialEq<&[u8]> for ServerShare {
             if found > 2 || part != NonDevicePart {
                 return false;
             }
+
+            // The server must also be a valid host name or address
+            if found == 0 && !is_valid_host(part) {
+                return false;
+            }
             found += 1;
         }
 
@@ -396,6 +401,61 @@ impl PartialEq<&[u8]> for FileExtension {
 
 mk_reverse_equal!(FileExtension, &[u8]);
 
+// ===========================================================================
+// ShortNameChar
+// ===========================================================================
+
+// A character that can appear in an 8.3 short name. Letters are accepted in
+// either case since names are matched case insensitively.
+#[derive(Debug)]
+pub struct ShortNameChar;
+
+impl PartialEq<u8> for ShortNameChar {
+    fn eq(&self, other: &u8) -> bool {
+        other.is_ascii_alphanumeric() || SHORT_NAME_SYMBOLS.contains(other)
+    }
+}
+
+mk_reverse_equal!(ShortNameChar, u8);
+
+// ===========================================================================
+// ShortName
+// ===========================================================================
+
+// An 8.3 name that was generated for a longer one, eg PROGRA~1 or
+// MYDOCU~2.TXT, whose base name ends in ~ and a number
+#[derive(Debug)]
+pub struct ShortName;
+
+impl PartialEq<&[u8]> for ShortName {
+    fn eq(&self, other: &&[u8]) -> bool {
+        let (base, ext) = match other.iter().rposition(|&b| b == b'.') {
+            Some(i) => (&other[..i], &other[i + 1..]),
+            None => (*other, &[][..]),
+        };
+        let has_dot = base.len() < other.len();
+        if base.len() > 8 || ext.len() > 3 || (has_dot && ext.is_empty()) {
+            return false;
+        }
+
+        let tilde = match base.iter().rposition(|&b| b == b'~') {
+            Some(i) => i,
+            None => return false,
+        };
+        let (stem, number) = (&base[..tilde], &base[tilde + 1..]);
+        let valid_number = match number.first() {
+            Some(b'1'..=b'9') => number.iter().all(u8::is_ascii_digit),
+            _ => false,
+        };
+
+        !stem.is_empty()
+            && valid_number
+            && stem.iter().chain(ext).all(|&b| b == ShortNameChar)
+    }
+}
+
+mk_reverse_equal!(ShortName, &[u8]);
+
 // ===========================================================================
 // Tests
 // ===========================================================================
@@ -406,6 +466,11 @@ mod test {
     // Exclude restricted printable chars and any char with ascii code 0 - 31
     const CHAR_REGEX: &str = r#"[/\\<>:"|?*\x00-\x1F]"#;
     const COMP_REGEX: &str = r#"[^/\\<>:"|?*\x00-\x1F]+"#;
+
+    // Host names made of letters, digits, - and _, which are valid as the
+    // server of a UNC path unless every label is a number
+    const HOST_REGEX: &str =
+        r#"[a-z0-9_]{1,15}(-[a-z0-9_]{1,15}){0,2}(\.[a-z0-9_]{1,15}){0,3}"#;
     const VALID_CHARS_NOEXT: &str =
         r#"[^./\\<>:"|?*\x00-\x1F]*[^./\\<>:"|?*\x00-\x1F ]+"#;
 
@@ -424,23 +489,23 @@ mod test {
         proptest! {
             #[test]
             fn valid_value(s in r#"[a-zA-Z][:]"#) {
-                let arr: Vec<u8> = s.bytes().map(|c| c as u8).collect();
+                let arr: Vec<u8> = s.bytes().collect();
                 prop_assert_eq!(Disk, &arr[..]);
             }
 
             #[test]
             fn ne_len_value(s in r#".*"#) {
                 prop_assume!(s.len() != 2);
-                let arr: Vec<u8> = s.bytes().map(|c| c as u8).collect();
+                let arr: Vec<u8> = s.bytes().collect();
                 prop_assert_ne!(Disk, &arr[..]);
             }
 
             #[test]
             fn ne_value(s in r#".."#) {
-                let arr: Vec<u8> = s.bytes().map(|c| c as u8).collect();
+                let arr: Vec<u8> = s.bytes().collect();
                 prop_assume!(arr.len() == 2);
                 prop_assume!(!DRIVE_LETTERS.contains(&(arr[0] as char)) ||
-                             arr[1] != ':' as u8);
+                             arr[1] != b':');
 
                 prop_assert_ne!(Disk, &arr[..]);
             }
@@ -462,23 +527,23 @@ mod test {
         proptest! {
             #[test]
             fn valid_value(s in r#"[a-zA-Z][:]\\"#) {
-                let arr: Vec<u8> = s.bytes().map(|c| c as u8).collect();
+                let arr: Vec<u8> = s.bytes().collect();
                 prop_assert_eq!(DiskRoot, &arr[..]);
             }
 
             #[test]
             fn ne_len_value(s in r#".*"#) {
                 prop_assume!(s.len() != 3);
-                let arr: Vec<u8> = s.bytes().map(|c| c as u8).collect();
+                let arr: Vec<u8> = s.bytes().collect();
                 prop_assert_ne!(DiskRoot, &arr[..]);
             }
 
             #[test]
             fn ne_value(s in r#"..."#) {
-                let arr: Vec<u8> = s.bytes().map(|c| c as u8).collect();
+                let arr: Vec<u8> = s.bytes().collect();
                 prop_assume!(arr.len() == 3);
                 prop_assume!(!DRIVE_LETTERS.contains(&(arr[0] as char)) ||
-                             arr[1] != ':' as u8 ||
+                             arr[1] != b':' ||
                              !SEPARATOR.contains(&arr[2]));
                 prop_assert_ne!(DiskRoot, &arr[..]);
             }
@@ -509,14 +574,14 @@ mod test {
         proptest! {
             #[test]
             fn any_separator(s in r#"[/\\][/\\]"#) {
-                let arr: Vec<u8> = s.bytes().map(|c| c as u8).collect();
+                let arr: Vec<u8> = s.bytes().collect();
                 prop_assert_eq!(DoubleSlash, &arr[..]);
             }
 
             #[test]
             fn ne_len_value(s in r#".*"#) {
                 prop_assume!(s.len() != 2);
-                let arr: Vec<u8> = s.bytes().map(|c| c as u8).collect();
+                let arr: Vec<u8> = s.bytes().collect();
                 prop_assert_ne!(DoubleSlash, &arr[..]);
             }
 
@@ -525,7 +590,7 @@ mod test {
                 prop_assume!(s.len() == 2);
                 prop_assume!(!s.bytes().take(2).all(|c| SEPARATOR.contains(&c)));
 
-                let arr: Vec<u8> = s.bytes().map(|c| c as u8).collect();
+                let arr: Vec<u8> = s.bytes().collect();
                 prop_assert_ne!(DoubleSlash, &arr[..]);
             }
         }
@@ -571,7 +636,7 @@ mod test {
                         prop_assume!(s.len() == 2);
 
                         let arr: Vec<u8> = s.bytes().map(|b| b as u8).collect();
-                        prop_assume!(arr[0] != $first_char as u8 ||
+                        prop_assume!(arr[0] != $first_char ||
                                      !SEPARATOR.contains(&arr[1]));
 
                         let arr: Vec<u8> = s.bytes().map(|c| c as u8).collect();
@@ -582,9 +647,9 @@ mod test {
         };
     }
 
-    mk_slash_type_test!(questionslash, QuestionSlash, r#"\?[/\\]"#, '?');
+    mk_slash_type_test!(questionslash, QuestionSlash, r#"\?[/\\]"#, b'?');
 
-    mk_slash_type_test!(dotslash, DotSlash, r#"\.[/\\]"#, '.');
+    mk_slash_type_test!(dotslash, DotSlash, r#"\.[/\\]"#, b'.');
 
     mod device {
         use super::*;
@@ -609,8 +674,7 @@ mod test {
             {
                 let arr: Vec<&[u8]> = RESERVED_NAMES.iter()
                     .map(|s| s.as_bytes()).collect();
-                let val: Vec<u8> = arr[i].iter()
-                    .map(|&b| b as u8).collect();
+                let val: Vec<u8> = arr[i].to_vec();
                 prop_assert_eq!(Device, &val[..]);
 
                 let mut val_ext: Vec<u8> = val.clone();
@@ -624,7 +688,7 @@ mod test {
             fn ne_value(s in r#".*"#) {
                 prop_assume!(!RESERVED_NAMES.contains(&s));
 
-                let arr: Vec<u8> = s.bytes().map(|c| c as u8).collect();
+                let arr: Vec<u8> = s.bytes().collect();
                 prop_assert_ne!(Device, &arr[..]);
             }
         }
@@ -698,15 +762,14 @@ mod test {
                            c_char in r#"[cC]"#)
             {
                 let unc_word = format!("{}{}{}", u_char, n_char, c_char);
-                let bytes: Vec<u8> = unc_word.bytes()
-                    .map(|b| b as u8).collect();
+                let bytes: Vec<u8> = unc_word.bytes().collect();
                 prop_assert_eq!(UNCPart, &bytes[..]);
             }
 
             #[test]
             fn ne_len_value(s in r#".*"#) {
                 prop_assume!(s.len() != 3);
-                let arr: Vec<u8> = s.bytes().map(|c| c as u8).collect();
+                let arr: Vec<u8> = s.bytes().collect();
                 prop_assert_ne!(UNCPart, &arr[..]);
             }
 
@@ -715,11 +778,11 @@ mod test {
                 prop_assume!(s.len() == 3);
                 let bytes = s.as_bytes();
 
-                prop_assume!(!UNC_LETTERS.contains(&(bytes[0] as u8))
-                             && !UNC_LETTERS.contains(&(bytes[1] as u8))
-                             && !UNC_LETTERS.contains(&(bytes[2] as u8)));
+                prop_assume!(!UNC_LETTERS.contains(&bytes[0])
+                             && !UNC_LETTERS.contains(&bytes[1])
+                             && !UNC_LETTERS.contains(&bytes[2]));
 
-                let arr: Vec<u8> = bytes.iter().map(|&c| c as u8).collect();
+                let arr: Vec<u8> = bytes.to_vec();
                 prop_assert_ne!(UNCPart, &arr[..]);
             }
         }
@@ -745,15 +808,14 @@ mod test {
                            sep in r#"[/\\]"#)
             {
                 let unc_word = format!("{}{}{}{}", u_char, n_char, c_char, sep);
-                let bytes: Vec<u8> = unc_word.bytes()
-                    .map(|b| b as u8).collect();
+                let bytes: Vec<u8> = unc_word.bytes().collect();
                 prop_assert_eq!(UNCRootPart, &bytes[..]);
             }
 
             #[test]
             fn ne_len_value(s in r#".*"#) {
                 prop_assume!(s.len() != 4);
-                let arr: Vec<u8> = s.bytes().map(|c| c as u8).collect();
+                let arr: Vec<u8> = s.bytes().collect();
                 prop_assert_ne!(UNCPart, &arr[..]);
             }
 
@@ -763,7 +825,7 @@ mod test {
 
                 prop_assume!(&bytes[..3] != UNCPart || bytes[3] != Separator);
 
-                let arr: Vec<u8> = bytes.iter().map(|&c| c as u8).collect();
+                let arr: Vec<u8> = bytes.to_vec();
                 prop_assert_ne!(UNCRootPart, &arr[..]);
             }
         }
@@ -793,7 +855,7 @@ mod test {
                     !INVALID_LAST_CHAR.contains(&s.as_bytes()[s.len() - 1])
                 );
 
-                let bytes: Vec<u8> = s.bytes().map(|b| b as u8).collect();
+                let bytes: Vec<u8> = s.bytes().collect();
                 prop_assume!(&bytes[..] != UNCPart && &bytes[..] != Device);
                 prop_assert_eq!(NonUNCPart, &bytes[..]);
             }
@@ -804,8 +866,7 @@ mod test {
                                 c_char in r#"[cC]"#)
             {
                 let unc_word = format!("{}{}{}", u_char, n_char, c_char);
-                let bytes: Vec<u8> = unc_word.bytes()
-                    .map(|b| b as u8).collect();
+                let bytes: Vec<u8> = unc_word.bytes().collect();
                 prop_assert_ne!(NonUNCPart, &bytes[..]);
             }
 
@@ -813,28 +874,25 @@ mod test {
             fn ne_device_value(i in 0..RESERVED_NAMES.len()) {
                 let arr: Vec<&[u8]> = RESERVED_NAMES.iter()
                     .map(|s| s.as_bytes()).collect();
-                let val: Vec<u8> = arr[i].iter()
-                    .map(|&b| b as u8).collect();
+                let val: Vec<u8> = arr[i].to_vec();
                 prop_assert_ne!(NonUNCPart, &val[..]);
             }
 
             #[test]
             fn ne_has_separator(s in r#".*"#, sep in r#"[/\\]"#) {
-                let mut bytes: Vec<u8> = s.bytes()
-                    .map(|b| b as u8).collect();
+                let mut bytes: Vec<u8> = s.bytes().collect();
                 prop_assume!(&bytes[..] != UNCPart
                              && &bytes[..] != Device);
 
                 if bytes.iter().all(|b| !SEPARATOR.contains(b)) {
-                    bytes.push(sep.bytes().nth(0).unwrap() as u8);
+                    bytes.push(sep.as_bytes()[0]);
                 }
                 prop_assert_ne!(NonUNCPart, &bytes[..]);
             }
 
             #[test]
             fn ne_invalid_lastchar(s in r#".*[ .]"#) {
-                let bytes: Vec<u8> = s.bytes()
-                    .map(|b| b as u8).collect();
+                let bytes: Vec<u8> = s.bytes().collect();
                 prop_assume!(&bytes[..] != UNCPart
                              && &bytes[..] != Device);
 
@@ -843,8 +901,7 @@ mod test {
 
             #[test]
             fn ne_invalid_dirname(s in r#"([.])|([.][.])"#) {
-                let bytes: Vec<u8> = s.bytes()
-                    .map(|b| b as u8).collect();
+                let bytes: Vec<u8> = s.bytes().collect();
                 prop_assert_ne!(NonUNCPart, &bytes[..]);
             }
         }
@@ -870,7 +927,7 @@ mod test {
         proptest! {
             #[test]
             fn valid_value(s in COMP_REGEX) {
-                let bytes: Vec<u8> = s.bytes().map(|b| b as u8).collect();
+                let bytes: Vec<u8> = s.bytes().collect();
                 prop_assume!(
                     &bytes[..] != Device
                     && !INVALID_LAST_CHAR
@@ -885,8 +942,7 @@ mod test {
                                 c_char in r#"[cC]"#)
             {
                 let unc_word = format!("{}{}{}", u_char, n_char, c_char);
-                let bytes: Vec<u8> = unc_word.bytes()
-                    .map(|b| b as u8).collect();
+                let bytes: Vec<u8> = unc_word.bytes().collect();
                 prop_assert_eq!(NonDevicePart, &bytes[..]);
             }
 
@@ -894,27 +950,24 @@ mod test {
             fn ne_device_value(i in 0..RESERVED_NAMES.len()) {
                 let arr: Vec<&[u8]> = RESERVED_NAMES.iter()
                     .map(|s| s.as_bytes()).collect();
-                let val: Vec<u8> = arr[i].iter()
-                    .map(|&b| b as u8).collect();
+                let val: Vec<u8> = arr[i].to_vec();
                 prop_assert_ne!(NonDevicePart, &val[..]);
             }
 
             #[test]
             fn ne_has_separator(s in r#".*"#, sep in r#"[/\\]"#) {
-                let mut bytes: Vec<u8> = s.bytes()
-                    .map(|b| b as u8).collect();
+                let mut bytes: Vec<u8> = s.bytes().collect();
                 prop_assume!(&bytes[..] != Device);
 
                 if bytes.iter().all(|b| !SEPARATOR.contains(b)) {
-                    bytes.push(sep.bytes().nth(0).unwrap() as u8);
+                    bytes.push(sep.as_bytes()[0]);
                 }
                 prop_assert_ne!(NonDevicePart, &bytes[..]);
             }
 
             #[test]
             fn ne_invalid_lastchar(s in r#".*[ .]"#) {
-                let bytes: Vec<u8> = s.bytes()
-                    .map(|b| b as u8).collect();
+                let bytes: Vec<u8> = s.bytes().collect();
                 prop_assume!(&bytes[..] != ParentDir
                              && &bytes[..] != CurrentDir);
 
@@ -923,8 +976,7 @@ mod test {
 
             #[test]
             fn ne_reserved_dirname(s in r#"([.])|([.][.])"#) {
-                let bytes: Vec<u8> = s.bytes()
-                    .map(|b| b as u8).collect();
+                let bytes: Vec<u8> = s.bytes().collect();
                 prop_assert_eq!(NonDevicePart, &bytes[..]);
             }
         }
@@ -936,6 +988,7 @@ mod test {
         use crate::windows::path_type::{
             Device, NonDevicePart, ServerShare, RESERVED_NAMES, SEPARATOR,
         };
+        use crate::windows::unc::is_valid_host;
 
         use proptest::{
             prop_assert, prop_assert_eq, prop_assert_ne, prop_assume, proptest,
@@ -946,25 +999,35 @@ mod test {
             assert_eq!(ServerShare, ServerShare);
         }
 
+        #[test]
+        fn ne_invalid_host() {
+            let paths: Vec<&[u8]> =
+                vec![br"bad host\share", br"-srv.com\share", br"1.2.3\share"];
+            for path in paths {
+                assert_ne!(ServerShare, path);
+            }
+        }
+
         proptest! {
             #[test]
-            fn valid_value(server in COMP_REGEX,
+            fn valid_value(server in HOST_REGEX,
                            share in COMP_REGEX,
                            sep in r#"[/\\]"#)
             {
                 let server_bytes: Vec<u8> = server
-                    .bytes().map(|b| b as u8).collect();
+                    .bytes().collect();
                 let share_bytes: Vec<u8> = share
-                    .bytes().map(|b| b as u8).collect();
+                    .bytes().collect();
                 prop_assume!(
                     &server_bytes[..] == NonDevicePart
                     && &share_bytes[..] == NonDevicePart
+                    && is_valid_host(&server_bytes)
                 );
 
                 let mut server_share: Vec<u8> = Vec::new();
                 server_share.extend(server_bytes.iter());
                 server_share.push(
-                    *sep.as_bytes().iter().nth(0).unwrap() as u8
+                    *sep.as_bytes().first().unwrap()
                 );
                 server_share.extend(share_bytes.iter());
 
@@ -978,18 +1041,18 @@ mod test {
                                  sep in r#"[/\\]"#)
             {
                 let server_bytes: Vec<u8> = server
-                    .bytes().map(|b| b as u8).collect();
+                    .bytes().collect();
                 let share_bytes: Vec<u8> = share
-                    .bytes().map(|b| b as u8).collect();
+                    .bytes().collect();
                 let other_bytes: Vec<u8> = other
-                    .bytes().map(|b| b as u8).collect();
+                    .bytes().collect();
                 prop_assume!(
                     &server_bytes[..] == NonDevicePart
                     && &share_bytes[..] == NonDevicePart
                 );
 
                 let mut server_share: Vec<u8> = Vec::new();
-                let sep_elem = *sep.as_bytes().iter().nth(0).unwrap() as u8;
+                let sep_elem = *sep.as_bytes().first().unwrap();
                 server_share.extend(server_bytes.iter());
                 server_share.push(sep_elem);
                 server_share.extend(share_bytes.iter());
@@ -1001,7 +1064,7 @@ mod test {
 
             #[test]
             fn ne_no_separator(s in r#".*"#) {
-                let bytes: Vec<u8> = s.bytes().map(|b| b as u8).collect();
+                let bytes: Vec<u8> = s.bytes().collect();
                 prop_assume!(&bytes[..] != Device
                              && !bytes.iter().any(|b| SEPARATOR.contains(b)));
                 prop_assert_ne!(ServerShare, &bytes[..]);
@@ -1013,8 +1076,7 @@ mod test {
                                 sep in r#"[/\\]"#)
             {
 
-                let share_bytes: Vec<u8> = share.bytes()
-                    .map(|b| b as u8).collect();
+                let share_bytes: Vec<u8> = share.bytes().collect();
                 prop_assume!(
                     &share_bytes[..] != Device
                     && !share_bytes.iter().any(|b| SEPARATOR.contains(b))
@@ -1022,13 +1084,12 @@ mod test {
 
                 let arr: Vec<&[u8]> = RESERVED_NAMES.iter()
                     .map(|s| s.as_bytes()).collect();
-                let server: Vec<u8> = arr[i].iter()
-                    .map(|&b| b as u8).collect();
+                let server: Vec<u8> = arr[i].to_vec();
 
                 let mut server_share: Vec<u8> = Vec::new();
                 server_share.extend(server.iter());
                 server_share.push(
-                    *sep.as_bytes().iter().nth(0).unwrap() as u8
+                    *sep.as_bytes().first().unwrap()
                 );
                 server_share.extend(share_bytes.iter());
 
@@ -1041,8 +1102,7 @@ mod test {
                                sep in r#"[/\\]"#)
             {
 
-                let server_bytes: Vec<u8> = server.bytes()
-                    .map(|b| b as u8).collect();
+                let server_bytes: Vec<u8> = server.bytes().collect();
                 prop_assume!(
                     &server_bytes[..] != Device
                     && !server_bytes.iter().any(|b| SEPARATOR.contains(b))
@@ -1050,13 +1110,12 @@ mod test {
 
                 let arr: Vec<&[u8]> = RESERVED_NAMES.iter()
                     .map(|s| s.as_bytes()).collect();
-                let share: Vec<u8> = arr[i].iter()
-                    .map(|&b| b as u8).collect();
+                let share: Vec<u8> = arr[i].to_vec();
 
                 let mut server_share: Vec<u8> = Vec::new();
                 server_share.extend(server_bytes.iter());
                 server_share.push(
-                    *sep.as_bytes().iter().nth(0).unwrap() as u8
+                    *sep.as_bytes().first().unwrap()
                 );
                 server_share.extend(share.iter());
 
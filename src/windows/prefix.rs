@@ -0,0 +1,226 @@
+// src/windows/prefix.rs
+// Copyright (C) 2019 authors and contributors (see AUTHORS file)
+//
+// This file is released under the MIT License.
+
+// ===========================================================================
+// Imports
+// ===========================================================================
+
+// Stdlib imports
+use std::ffi::OsStr;
+use std::path::Prefix;
+
+// Third-party imports
+
+// Local imports
+use crate::common::string::{as_osstr, as_str, ascii_uppercase};
+use crate::path::{SystemSeq, SystemStr};
+use crate::windows::unc::is_valid_host;
+
+// ===========================================================================
+// PrefixKind
+// ===========================================================================
+
+// A finer classification of a prefix than std::path::Prefix, which has no
+// variants for the special namespaces below and reports them as DeviceNS
+// or Verbatim
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PrefixKind<'path> {
+    Verbatim(&'path OsStr),
+    VerbatimUNC(&'path OsStr, &'path OsStr),
+    VerbatimDisk(u8),
+    DeviceNS(&'path OsStr),
+    UNC(&'path OsStr, &'path OsStr),
+    Disk(u8),
+
+    // \\.\pipe, with the name of the pipe in the components after it
+    Pipe,
+
+    // \\.\mailslot, with the name of the mailslot in the components after it
+    Mailslot,
+
+    // \\.\PhysicalDriveN
+    PhysicalDrive(u32),
+
+    // \\?\Volume{GUID}, holding the GUID without its braces
+    Volume(&'path OsStr),
+
+    // \\?\GLOBALROOT\Device\Name, holding the object path after GLOBALROOT,
+    // eg Device\HarddiskVolume1
+    GlobalRoot(&'path OsStr),
+
+    // \??\ followed by an NT object name, eg \??\C:, holding the name
+    NtObject(&'path OsStr),
+}
+
+impl<'path> PrefixKind<'path> {
+    // raw is the text of the prefix that Iter parsed as kind
+    pub(crate) fn classify(raw: &'path [u8], kind: Prefix<'path>) -> Self {
+        if raw.starts_with(NT_PREFIX) {
+            let name = &raw[NT_PREFIX.len()..];
+            let name = name.strip_suffix(b"\\").unwrap_or(name);
+            return PrefixKind::NtObject(as_osstr(name));
+        }
+
+        match kind {
+            Prefix::Verbatim(name) => classify_verbatim(raw, name),
+            Prefix::VerbatimUNC(server, share) => {
+                PrefixKind::VerbatimUNC(server, share)
+            }
+            Prefix::VerbatimDisk(disk) => PrefixKind::VerbatimDisk(disk),
+            Prefix::DeviceNS(name) => classify_device(name),
+            Prefix::UNC(server, share) => PrefixKind::UNC(server, share),
+            Prefix::Disk(disk) => PrefixKind::Disk(disk),
+        }
+    }
+}
+
+// ===========================================================================
+// Helpers
+// ===========================================================================
+
+const NT_PREFIX: &[u8] = br"\??\";
+const VERBATIM_PREFIX: &[u8] = br"\\?\";
+const GLOBALROOT: &[u8] = b"GLOBALROOT";
+
+fn classify_verbatim<'path>(
+    raw: &'path [u8],
+    name: &'path OsStr,
+) -> PrefixKind<'path> {
+    let bytes = as_bytes(name);
+    if bytes.eq_ignore_ascii_case(GLOBALROOT) {
+        let start = VERBATIM_PREFIX.len() + GLOBALROOT.len();
+        let object = raw.get(start + 1..).unwrap_or(&[]);
+        return PrefixKind::GlobalRoot(as_osstr(object));
+    }
+
+    let volume = b"Volume{";
+    let is_volume = bytes.len() > volume.len()
+        && bytes[..volume.len()].eq_ignore_ascii_case(volume)
+        && bytes.ends_with(b"}");
+    if is_volume {
+        let guid = &bytes[volume.len()..bytes.len() - 1];
+        return PrefixKind::Volume(as_osstr(guid));
+    }
+
+    PrefixKind::Verbatim(name)
+}
+
+fn classify_device(name: &OsStr) -> PrefixKind<'_> {
+    let bytes = as_bytes(name);
+    if bytes.eq_ignore_ascii_case(b"pipe") {
+        return PrefixKind::Pipe;
+    } else if bytes.eq_ignore_ascii_case(b"mailslot") {
+        return PrefixKind::Mailslot;
+    }
+
+    let drive = b"PhysicalDrive";
+    if bytes.len() > drive.len()
+        && bytes[..drive.len()].eq_ignore_ascii_case(drive)
+    {
+        let number = &bytes[drive.len()..];
+        if number.iter().all(u8::is_ascii_digit) {
+            if let Ok(n) = as_str(number).parse() {
+                return PrefixKind::PhysicalDrive(n);
+            }
+        }
+    }
+
+    PrefixKind::DeviceNS(name)
+}
+
+fn as_bytes(name: &OsStr) -> &[u8] {
+    SystemStr::new(name).as_bytes()
+}
+
+// Length of the first component of path, up to the next \
+fn name_len(path: &[u8]) -> usize {
+    path.iter().position(|&b| b == b'\\').unwrap_or(path.len())
+}
+
+// ===========================================================================
+// Matcher
+// ===========================================================================
+
+// Matches the prefixes that std::path has no notion of, and that would
+// otherwise be split up at the wrong place. Only \ is accepted as a
+// separator since these are all NT object paths. The prefix is returned
+// with the std::path::Prefix that comes closest to it, and a verbatim disk
+// prefix includes its trailing separator.
+pub(crate) fn match_object_prefix(path: &[u8]) -> Option<(usize, Prefix<'_>)> {
+    if path.starts_with(NT_PREFIX) {
+        match_nt(path)
+    } else if path.starts_with(VERBATIM_PREFIX) {
+        match_globalroot(path)
+    } else {
+        None
+    }
+}
+
+// \??\C:\, \??\UNC\server\share or \??\Name
+fn match_nt(path: &[u8]) -> Option<(usize, Prefix<'_>)> {
+    let start = NT_PREFIX.len();
+    let rest = &path[start..];
+    let name = &rest[..name_len(rest)];
+    if name.is_empty() {
+        return None;
+    }
+
+    let is_disk = name.len() == 2
+        && name[0].is_ascii_alphabetic()
+        && name[1] == b':'
+        && rest.len() > 2;
+    if is_disk {
+        let letter = ascii_uppercase(name[0]);
+        return Some((start + 3, Prefix::VerbatimDisk(letter)));
+    }
+
+    if name.eq_ignore_ascii_case(b"UNC") && rest.len() > name.len() {
+        let server_start = name.len() + 1;
+        let server_len = name_len(&rest[server_start..]);
+        let server = &rest[server_start..server_start + server_len];
+        let share_start = server_start + server_len + 1;
+        if is_valid_host(server) && share_start < rest.len() {
+            let share_len = name_len(&rest[share_start..]);
+            if share_len > 0 {
+                let share = &rest[share_start..share_start + share_len];
+                let prefix =
+                    Prefix::VerbatimUNC(as_osstr(server), as_osstr(share));
+                return Some((start + share_start + share_len, prefix));
+            }
+        }
+    }
+
+    Some((start + name.len(), Prefix::Verbatim(as_osstr(name))))
+}
+
+// \\?\GLOBALROOT\Device\Name, where the device object is part of the prefix
+fn match_globalroot(path: &[u8]) -> Option<(usize, Prefix<'_>)> {
+    let start = VERBATIM_PREFIX.len();
+    let rest = &path[start..];
+    let name_end = name_len(rest);
+    if !rest[..name_end].eq_ignore_ascii_case(GLOBALROOT) {
+        return None;
+    }
+
+    let device = b"\\Device\\";
+    let object = &rest[name_end..];
+    let is_device = object.len() > device.len()
+        && object[..device.len()].eq_ignore_ascii_case(device);
+    if !is_device {
+        return None;
+    }
+
+    let object_len = device.len() + name_len(&object[device.len()..]);
+    if object_len == device.len() {
+        return None;
+    }
+
+    let name = as_osstr(&rest[..name_end]);
+    Some((start + name_end + object_len, Prefix::Verbatim(name)))
+}
+
+// ===========================================================================
+//
+// ===========================================================================
@@ -0,0 +1,187 @@
+// src/windows/private_use.rs
+// Copyright (C) 2019 authors and contributors (see AUTHORS file)
+//
+// This file is released under the MIT License.
+
+// ===========================================================================
+// Imports
+// ===========================================================================
+
+// Stdlib imports
+use std::ffi::{OsStr, OsString};
+
+// Third-party imports
+
+// Local imports
+use super::path_type::ValidLastChar;
+use super::{is_reserved_name, RESTRICTED_CHARS};
+use crate::common::string::as_osstr;
+use crate::path::{SystemSeq, SystemStr};
+
+// ===========================================================================
+// Private use mapping
+// ===========================================================================
+
+// Maps the characters of a Unix file name that Windows does not allow to
+// the Unicode private use area, the way WSL and Cygwin store such names on
+// NTFS: an ascii character c becomes U+F000 + c, eg a:b becomes a\u{f03a}b.
+// A trailing dot or space is mapped too, so the result is always a valid
+// Windows name unless it is a reserved one.
+pub fn encode_private_use(name: &OsStr) -> OsString {
+    let name = SystemStr::new(name).as_bytes();
+    let last = name.len().saturating_sub(1);
+    let invalid_last = name != ValidLastChar;
+
+    let mut ret = Vec::with_capacity(name.len());
+    for (i, &b) in name.iter().enumerate() {
+        if RESTRICTED_CHARS.contains(&b) || (i == last && invalid_last) {
+            push_private_use(&mut ret, b);
+        } else {
+            ret.push(b);
+        }
+    }
+    as_osstr(&ret).to_os_string()
+}
+
+// Reverses encode_private_use(), along with the mapping of the first letter
+// of a reserved name that ConvertPolicy can apply. Only the characters that
+// the mapping gives are restored, ie the ones that Windows does not allow
+// except / and NUL, a trailing dot or space, and a first letter that makes
+// the name a reserved one. Any other private use character is left alone,
+// so a Unix name that holds one converts back unchanged. A Unix name that
+// already holds one of the mapped characters in a place where it would be
+// restored, eg a\u{f03a}b, still comes back as a different name.
+pub fn decode_private_use(name: &OsStr) -> OsString {
+    let name = decode_private_use_bytes(SystemStr::new(name).as_bytes());
+    as_osstr(&name).to_os_string()
+}
+
+// ===========================================================================
+// Helpers
+// ===========================================================================
+
+// U+F000 is EF 80 80 in UTF-8, and the ascii character is held in the low 7
+// bits of the last 2 bytes
+const LEAD: u8 = 0xEF;
+
+pub(crate) fn push_private_use(name: &mut Vec<u8>, b: u8) {
+    debug_assert!(b.is_ascii());
+    name.extend_from_slice(&[LEAD, 0x80 | (b >> 6), 0x80 | (b & 0x3F)]);
+}
+
+// The ascii character that the private use character at the start of name
+// stands for
+pub(crate) fn private_use_byte(name: &[u8]) -> Option<u8> {
+    match name.get(..3)? {
+        &[LEAD, hi @ 0x80..=0x81, lo @ 0x80..=0xBF] => {
+            Some(((hi & 0x01) << 6) | (lo & 0x3F))
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn decode_private_use_bytes(name: &[u8]) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(name.len());
+    let mut i = 0;
+    if let Some(b) = private_use_byte(name) {
+        let mut decoded = vec![b];
+        decoded.extend_from_slice(&name[3..]);
+        if is_reserved_name(&decoded) {
+            ret.push(b);
+            i += 3;
+        }
+    }
+
+    while i < name.len() {
+        let is_last = i + 3 == name.len();
+        match private_use_byte(&name[i..]) {
+            Some(b) if b != 0 && b != b'/' && RESTRICTED_CHARS.contains(&b) => {
+                ret.push(b);
+                i += 3;
+            }
+            Some(b) if is_last && (b == b'.' || b == b' ') => {
+                ret.push(b);
+                i += 3;
+            }
+            _ => {
+                ret.push(name[i]);
+                i += 1;
+            }
+        }
+    }
+    ret
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode() {
+        let names = vec![
+            ("a:b", "a\u{f03a}b"),
+            (
+                r#"<>"|?*\"#,
+                "\u{f03c}\u{f03e}\u{f022}\u{f07c}\u{f03f}\u{f02a}\u{f05c}",
+            ),
+            ("tab\there", "tab\u{f009}here"),
+            ("dots..", "dots.\u{f02e}"),
+            ("space ", "space\u{f020}"),
+            ("plain.txt", "plain.txt"),
+            ("", ""),
+        ];
+        for (unix, windows) in names {
+            let encoded = encode_private_use(OsStr::new(unix));
+            assert_eq!(encoded, OsStr::new(windows), "{}", unix);
+            assert_eq!(decode_private_use(&encoded), OsStr::new(unix));
+        }
+    }
+
+    #[test]
+    fn decode_leaves_other_chars() {
+        let names = [
+            "\u{f000}",
+            "\u{f02f}",
+            "\u{f080}",
+            "\u{efff}",
+            "老虎",
+            "\u{f061}bc",
+            "a\u{f02e}b",
+            "\u{f020}x",
+            "\u{f06e}ux",
+        ];
+        for name in names.iter() {
+            let decoded = decode_private_use(OsStr::new(name));
+            assert_eq!(decoded, OsStr::new(name));
+        }
+
+        // The first letter of a reserved name is restored
+        let decoded = decode_private_use(OsStr::new("\u{f06e}ul"));
+        assert_eq!(decoded, OsStr::new("nul"));
+    }
+
+    #[test]
+    fn round_trip_private_use_names() {
+        let names = [
+            "\u{f061}bc",
+            "x\u{f02e}y",
+            "\u{f020}lead",
+            "\u{f080}\u{f0ff}",
+            "a:b\u{f041}",
+            "dots\u{f02e}.",
+            "\u{f06e}ux",
+        ];
+        for name in names.iter() {
+            let encoded = encode_private_use(OsStr::new(name));
+            assert_eq!(decode_private_use(&encoded), OsStr::new(name));
+        }
+    }
+}
+
+// ===========================================================================
+//
+// ===========================================================================
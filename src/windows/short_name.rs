@@ -0,0 +1,161 @@
+// src/windows/short_name.rs
+// Copyright (C) 2019 authors and contributors (see AUTHORS file)
+//
+// This file is released under the MIT License.
+
+// ===========================================================================
+// Imports
+// ===========================================================================
+
+// Stdlib imports
+use std::ffi::{OsStr, OsString};
+
+// Third-party imports
+
+// Local imports
+use super::path_type::{ShortName, ShortNameChar};
+use crate::common::string::as_osstr;
+use crate::path::{SystemSeq, SystemStr};
+
+// ===========================================================================
+// Short names
+// ===========================================================================
+
+// The first short name that NTFS would generate for a long file name. The
+// name is uppercased, spaces and all but the last dot are dropped, and any
+// character that is not allowed in a short name becomes _. If anything had
+// to be dropped, replaced or cut off to fit 8.3, the base name is cut to 6
+// characters and ~1 is added to it, eg Program Files becomes PROGRA~1 and
+// My Document.txt becomes MYDOCU~1.TXT. A name that already fits 8.3 is its
+// own short name.
+pub fn short_name_candidate(name: &OsStr) -> OsString {
+    let name = name.to_string_lossy();
+
+    // Leading dots do not start an extension
+    let trimmed = name.trim_start_matches('.');
+    let (base, ext) = match trimmed.rfind('.') {
+        Some(i) => (&trimmed[..i], &trimmed[i + 1..]),
+        None => (trimmed, ""),
+    };
+
+    let mut lossy = trimmed.len() < name.len();
+    let mut short_chars = |part: &str| -> String {
+        let mut ret = String::with_capacity(part.len());
+        for c in part.chars() {
+            match c {
+                ' ' | '.' => lossy = true,
+                c if c.is_ascii() && c as u8 == ShortNameChar => {
+                    ret.push(c.to_ascii_uppercase())
+                }
+                _ => {
+                    lossy = true;
+                    ret.push('_');
+                }
+            }
+        }
+        ret
+    };
+    let (mut base, mut ext) = (short_chars(base), short_chars(ext));
+
+    if lossy || base.is_empty() || base.len() > 8 || ext.len() > 3 {
+        base.truncate(6);
+        base.push_str("~1");
+        ext.truncate(3);
+    }
+
+    if !ext.is_empty() {
+        base.push('.');
+        base.push_str(&ext);
+    }
+    OsString::from(base)
+}
+
+// Whether short could be a short name that NTFS generated for long. NTFS
+// adds ~1, ~2 and so on to the first 6 characters of the candidate (fewer
+// once the number needs more digits), and after a few collisions replaces
+// all but the first 2 characters with 4 hex digits of a hash.
+pub(crate) fn is_short_alias(short: &[u8], long: &[u8]) -> bool {
+    if short != ShortName {
+        return false;
+    }
+
+    let candidate = short_name_candidate(as_osstr(long));
+    let candidate = SystemStr::new(&candidate).as_bytes();
+    let (cand_base, cand_ext) = split_ext(candidate);
+    let cand_stem = match cand_base.iter().position(|&b| b == b'~') {
+        Some(i) => &cand_base[..i],
+
+        // The long name is its own short name
+        None => return false,
+    };
+
+    let (base, ext) = split_ext(short);
+    let tilde = base.iter().rposition(|&b| b == b'~').unwrap_or(0);
+    let stem = &base[..tilde];
+    if !ext.eq_ignore_ascii_case(cand_ext) {
+        return false;
+    }
+
+    let is_prefix = stem.len() <= cand_stem.len()
+        && stem.eq_ignore_ascii_case(&cand_stem[..stem.len()]);
+    let is_hashed = stem.len() > 4 && {
+        let (start, hash) = stem.split_at(stem.len() - 4);
+        start.len() <= 2
+            && cand_stem.len() >= start.len()
+            && start.eq_ignore_ascii_case(&cand_stem[..start.len()])
+            && hash.iter().all(u8::is_ascii_hexdigit)
+    };
+    is_prefix || is_hashed
+}
+
+fn split_ext(name: &[u8]) -> (&[u8], &[u8]) {
+    match name.iter().rposition(|&b| b == b'.') {
+        Some(i) => (&name[..i], &name[i + 1..]),
+        None => (name, &[][..]),
+    }
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn candidates() {
+        let names = vec![
+            ("Program Files", "PROGRA~1"),
+            ("My Document.txt", "MYDOCU~1.TXT"),
+            ("readme.txt", "README.TXT"),
+            ("archive.tar.gz", "ARCHIV~1.GZ"),
+            ("file.html", "FILE~1.HTM"),
+            (".gitignore", "GITIGN~1"),
+            ("a+b.c", "A_B~1.C"),
+            ("café.txt", "CAF_~1.TXT"),
+        ];
+        for (long, short) in names {
+            let candidate = short_name_candidate(OsStr::new(long));
+            assert_eq!(candidate, OsStr::new(short), "{}", long);
+        }
+    }
+
+    #[test]
+    fn aliases() {
+        assert!(is_short_alias(b"PROGRA~1", b"Program Files"));
+        assert!(is_short_alias(b"progra~2", b"Program Files"));
+        assert!(is_short_alias(b"PROGR~12", b"Program Files"));
+        assert!(is_short_alias(b"PR3A2B~1", b"Program Files"));
+        assert!(is_short_alias(b"MYDOCU~2.TXT", b"My Document.txt"));
+
+        assert!(!is_short_alias(b"PROGRA~1", b"Programs"));
+        assert!(!is_short_alias(b"MYDOCU~2.DOC", b"My Document.txt"));
+        assert!(!is_short_alias(b"OTHER~1", b"Program Files"));
+        assert!(!is_short_alias(b"Program Files", b"PROGRA~1"));
+    }
+}
+
+// ===========================================================================
+//
+// ===========================================================================
@@ -0,0 +1,159 @@
+// src/windows/stream.rs
+// Copyright (C) 2019 authors and contributors (see AUTHORS file)
+//
+// This file is released under the MIT License.
+
+// ===========================================================================
+// Imports
+// ===========================================================================
+
+// Stdlib imports
+use std::ffi::OsStr;
+
+// Third-party imports
+
+// Local imports
+use super::RESTRICTED_CHARS;
+use crate::common::string::as_osstr;
+use crate::path::{SystemSeq, SystemStr};
+
+// ===========================================================================
+// FileStream
+// ===========================================================================
+
+// A file name split at the NTFS alternate data stream that it may name, eg
+// file.txt:Zone.Identifier:$DATA has the base name file.txt, the stream
+// name Zone.Identifier and the stream type $DATA
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct FileStream<'path> {
+    base: &'path OsStr,
+    stream: Option<&'path OsStr>,
+    stream_type: Option<&'path OsStr>,
+}
+
+impl<'path> FileStream<'path> {
+    // name is a file name that Iter has already checked
+    pub(crate) fn new(name: &'path OsStr) -> Self {
+        let bytes = SystemStr::new(name).as_bytes();
+        let colon = match stream_start(bytes) {
+            Some(i) => i,
+            None => {
+                return FileStream {
+                    base: name,
+                    stream: None,
+                    stream_type: None,
+                }
+            }
+        };
+
+        let rest = &bytes[colon + 1..];
+        let (stream, stream_type) = match stream_start(rest) {
+            Some(i) => (&rest[..i], Some(as_osstr(&rest[i + 1..]))),
+            None => (rest, None),
+        };
+        FileStream {
+            base: as_osstr(&bytes[..colon]),
+            stream: Some(as_osstr(stream)),
+            stream_type,
+        }
+    }
+
+    pub fn base(&self) -> &'path OsStr {
+        self.base
+    }
+
+    // The name of the stream, which is empty for the unnamed data stream
+    // (eg file.txt::$DATA), or None if the file name has no stream
+    pub fn stream(&self) -> Option<&'path OsStr> {
+        self.stream
+    }
+
+    pub fn stream_type(&self) -> Option<&'path OsStr> {
+        self.stream_type
+    }
+}
+
+// ===========================================================================
+// Helpers
+// ===========================================================================
+
+// Where the stream begins in a file name, which is at its first :
+pub(crate) fn stream_start(name: &[u8]) -> Option<usize> {
+    name.iter().position(|&b| b == b':')
+}
+
+// Checks everything after the first : of a file name, which is a stream
+// name optionally followed by : and a stream type such as $DATA. The stream
+// name can only be left out when a type is given.
+pub(crate) fn is_valid_stream(stream: &[u8]) -> bool {
+    let (name, stream_type) = match stream_start(stream) {
+        Some(i) => (&stream[..i], Some(&stream[i + 1..])),
+        None => (stream, None),
+    };
+
+    if name.iter().any(|b| RESTRICTED_CHARS.contains(b)) {
+        return false;
+    }
+
+    match stream_type {
+        Some(stream_type) => is_valid_stream_type(stream_type),
+        None => !name.is_empty(),
+    }
+}
+
+fn is_valid_stream_type(stream_type: &[u8]) -> bool {
+    match stream_type.split_first() {
+        Some((b'$', rest)) => {
+            !rest.is_empty()
+                && rest.iter().all(|&b| b.is_ascii_alphanumeric() || b == b'_')
+        }
+        _ => false,
+    }
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn valid_streams() {
+        let streams: Vec<&[u8]> =
+            vec![b"Zone.Identifier", b"Zone.Identifier:$DATA", b":$DATA"];
+        for stream in streams {
+            assert!(is_valid_stream(stream));
+        }
+    }
+
+    #[test]
+    fn invalid_streams() {
+        let streams: Vec<&[u8]> =
+            vec![b"", b":", b"a:", b"a:DATA", b"a:$", b"a:$DA:TA", b"a?b"];
+        for stream in streams {
+            assert!(!is_valid_stream(stream));
+        }
+    }
+
+    #[test]
+    fn split_name() {
+        let stream = FileStream::new(OsStr::new("a.txt:Zone:$DATA"));
+        assert_eq!(stream.base(), OsStr::new("a.txt"));
+        assert_eq!(stream.stream(), Some(OsStr::new("Zone")));
+        assert_eq!(stream.stream_type(), Some(OsStr::new("$DATA")));
+
+        let stream = FileStream::new(OsStr::new("a.txt::$DATA"));
+        assert_eq!(stream.stream(), Some(OsStr::new("")));
+
+        let stream = FileStream::new(OsStr::new("a.txt"));
+        assert_eq!(stream.base(), OsStr::new("a.txt"));
+        assert_eq!(stream.stream(), None);
+        assert_eq!(stream.stream_type(), None);
+    }
+}
+
+// ===========================================================================
+//
+// ===========================================================================
@@ -0,0 +1,293 @@
+// src/windows/unc.rs
+// Copyright (C) 2019 authors and contributors (see AUTHORS file)
+//
+// This file is released under the MIT License.
+
+// ===========================================================================
+// Imports
+// ===========================================================================
+
+// Stdlib imports
+use std::ffi::OsStr;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+// Third-party imports
+
+// Local imports
+use crate::common::string::{as_osstr, as_str};
+use crate::path::{SystemSeq, SystemStr};
+
+// ===========================================================================
+// UncHost
+// ===========================================================================
+
+// The server of a UNC prefix, eg server in \\server\share
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum UncHost<'path> {
+    Localhost,
+    Ipv4(Ipv4Addr),
+
+    // An IPv6 address written as a name under ipv6-literal.net, eg
+    // fe80--1s4.ipv6-literal.net for fe80::1%4, with its zone if it has one
+    Ipv6 {
+        addr: Ipv6Addr,
+        zone: Option<&'path OsStr>,
+    },
+
+    // A DNS or NetBIOS name
+    Name(&'path OsStr),
+}
+
+impl<'path> UncHost<'path> {
+    // Returns None if host is not a valid host name or address
+    pub fn parse(host: &'path OsStr) -> Option<Self> {
+        let bytes = SystemStr::new(host).as_bytes();
+        if bytes.eq_ignore_ascii_case(b"localhost") {
+            return Some(UncHost::Localhost);
+        }
+
+        let ipv6_end = bytes.len().checked_sub(IPV6_LITERAL.len());
+        if let Some(end) = ipv6_end {
+            if bytes[end..].eq_ignore_ascii_case(IPV6_LITERAL) {
+                return parse_ipv6_literal(&bytes[..end]);
+            }
+        }
+
+        let labels: Vec<&[u8]> = bytes.split(|&b| b == b'.').collect();
+        let is_numeric = labels
+            .iter()
+            .all(|label| label.iter().all(u8::is_ascii_digit));
+        if is_numeric {
+            // Anything that looks like an address must be a valid one
+            return as_str(bytes).parse().ok().map(UncHost::Ipv4);
+        }
+
+        // A name of a single label is a NetBIOS name, which can also hold
+        // punctuation, eg the wsl$ server that WSL shares its distributions
+        // from
+        let is_name = match labels.as_slice() {
+            [name] => is_valid_netbios_name(name),
+            _ => {
+                bytes.len() <= MAX_NAME_LEN
+                    && labels.iter().all(|label| is_valid_label(label))
+            }
+        };
+        if is_name {
+            Some(UncHost::Name(host))
+        } else {
+            None
+        }
+    }
+
+    // Whether the host is always this machine
+    pub fn is_local(&self) -> bool {
+        match self {
+            UncHost::Localhost => true,
+            UncHost::Ipv4(addr) => addr.is_loopback(),
+            UncHost::Ipv6 { addr, .. } => addr.is_loopback(),
+            UncHost::Name(_) => false,
+        }
+    }
+}
+
+// ===========================================================================
+// UncShare
+// ===========================================================================
+
+// The share of a UNC prefix, eg share in \\server\share
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum UncShare<'path> {
+    // The administrative share of a drive, eg C$, holding its uppercase
+    // drive letter
+    Disk(u8),
+
+    // ADMIN$, the Windows directory of the server
+    Admin,
+
+    // IPC$, used for named pipes
+    Ipc,
+
+    Name(&'path OsStr),
+}
+
+impl<'path> UncShare<'path> {
+    pub fn parse(share: &'path OsStr) -> Self {
+        let bytes = SystemStr::new(share).as_bytes();
+        match bytes {
+            [letter, b'$'] if letter.is_ascii_alphabetic() => {
+                UncShare::Disk(letter.to_ascii_uppercase())
+            }
+            _ if bytes.eq_ignore_ascii_case(b"ADMIN$") => UncShare::Admin,
+            _ if bytes.eq_ignore_ascii_case(b"IPC$") => UncShare::Ipc,
+            _ => UncShare::Name(share),
+        }
+    }
+}
+
+// ===========================================================================
+// Helpers
+// ===========================================================================
+
+const IPV6_LITERAL: &[u8] = b".ipv6-literal.net";
+
+// The longest DNS name and label, in bytes
+const MAX_NAME_LEN: usize = 255;
+const MAX_LABEL_LEN: usize = 63;
+
+// The punctuation that NetBIOS names can hold
+const NETBIOS_CHARS: &[u8] = b"!#$%&'()-@^_{}~";
+
+// Whether host can be used as the server of a UNC prefix
+pub(crate) fn is_valid_host(host: &[u8]) -> bool {
+    UncHost::parse(as_osstr(host)).is_some()
+}
+
+// Where the server of a path that starts like a UNC prefix ends, if the
+// server is not a valid host, eg \\bad host\share. Such a path is neither
+// a UNC path nor the rooted path that its separators would otherwise make
+// it, so it is reported as an invalid host.
+pub(crate) fn invalid_host_end(path: &[u8]) -> Option<usize> {
+    let is_sep = |b: &u8| *b == b'\\' || *b == b'/';
+    let rest = match path {
+        [a, b, rest @ ..] if is_sep(a) && is_sep(b) => rest,
+        _ => return None,
+    };
+
+    let host_len = rest.iter().position(is_sep)?;
+    let host = &rest[..host_len];
+    let has_share = matches!(rest.get(host_len + 1), Some(b) if !is_sep(b));
+    let is_device = host == b"?" || host == b".";
+    if host.is_empty() || is_device || !has_share || is_valid_host(host) {
+        None
+    } else {
+        Some(2 + host_len)
+    }
+}
+
+// A NetBIOS name can hold the punctuation in NETBIOS_CHARS as well as
+// letters and digits. NetBIOS names are at most 15 bytes, but a single label
+// may also be a DNS name, so it is allowed as long as a DNS label.
+fn is_valid_netbios_name(name: &[u8]) -> bool {
+    let valid_char = |b: &u8| {
+        !b.is_ascii() || b.is_ascii_alphanumeric() || NETBIOS_CHARS.contains(b)
+    };
+    !name.is_empty()
+        && name.len() <= MAX_LABEL_LEN
+        && name.iter().all(valid_char)
+}
+
+// A label of a host name can hold letters, digits, - and _, but cannot start
+// or end with -. Bytes outside of ascii are let through for international
+// names.
+fn is_valid_label(label: &[u8]) -> bool {
+    let valid_char = |&b: &u8| {
+        !b.is_ascii() || b.is_ascii_alphanumeric() || b == b'-' || b == b'_'
+    };
+    match (label.first(), label.last()) {
+        (Some(b'-'), _) | (_, Some(b'-')) => false,
+        (Some(_), Some(_)) => label.iter().all(valid_char),
+        _ => false,
+    }
+}
+
+// Parses the part of an ipv6-literal.net name before the domain, which has
+// - in place of : and s in place of %
+fn parse_ipv6_literal(name: &[u8]) -> Option<UncHost<'_>> {
+    let (addr, zone) = match name.iter().position(|&b| b == b's' || b == b'S') {
+        Some(i) => (&name[..i], Some(&name[i + 1..])),
+        None => (name, None),
+    };
+
+    let is_hex = |&b: &u8| b.is_ascii_hexdigit() || b == b'-';
+    if addr.is_empty() || !addr.iter().all(is_hex) {
+        return None;
+    }
+    let addr: String = as_str(addr)
+        .chars()
+        .map(|c| if c == '-' { ':' } else { c })
+        .collect();
+    let addr = addr.parse().ok()?;
+
+    let zone = match zone {
+        Some([]) => return None,
+        Some(zone) => Some(as_osstr(zone)),
+        None => None,
+    };
+    Some(UncHost::Ipv6 { addr, zone })
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn valid_hosts() {
+        let hosts: Vec<&[u8]> = vec![
+            b"server",
+            b"file-server_2",
+            b"files.example.com",
+            b"192.168.1.10",
+            b"localhost",
+            b"fe80--1s4.ipv6-literal.net",
+            b"wsl$",
+            b"ser$ver",
+            b"-server",
+            b"acme!#'",
+            b"{team}~",
+        ];
+        for host in hosts {
+            assert!(is_valid_host(host), "{}", as_str(host));
+        }
+    }
+
+    #[test]
+    fn invalid_hosts() {
+        let hosts: Vec<&[u8]> = vec![
+            b"",
+            b"bad host",
+            b"bad*host",
+            b"-server.example.com",
+            b"server-.example.com",
+            b"files..example.com",
+            b"999.1.1.1",
+            b"1.2.3",
+            b"acme!.example.com",
+            b"wsl$.example.com",
+            b"zz--1.ipv6-literal.net",
+            b"fe80--1s.ipv6-literal.net",
+        ];
+        for host in hosts {
+            assert!(!is_valid_host(host), "{}", as_str(host));
+        }
+    }
+
+    #[test]
+    fn ipv6_literal() {
+        let host = UncHost::parse(OsStr::new("fe80--1s4.ipv6-literal.net"));
+        let expected = UncHost::Ipv6 {
+            addr: "fe80::1".parse().unwrap(),
+            zone: Some(OsStr::new("4")),
+        };
+        assert_eq!(host, Some(expected));
+
+        let host = UncHost::parse(OsStr::new("--1.IPV6-LITERAL.NET"));
+        assert!(host.unwrap().is_local());
+    }
+
+    #[test]
+    fn shares() {
+        let parse = |s| UncShare::parse(OsStr::new(s));
+        assert_eq!(parse("c$"), UncShare::Disk(b'C'));
+        assert_eq!(parse("ADMIN$"), UncShare::Admin);
+        assert_eq!(parse("ipc$"), UncShare::Ipc);
+        assert_eq!(parse("cc$"), UncShare::Name(OsStr::new("cc$")));
+    }
+}
+
+// ===========================================================================
+//
+// ===========================================================================
@@ -27,7 +27,7 @@ mod parts {
         let path = UnixPath::new("/a/unix/path");
         let expected: Vec<OsString> = ["/", "a", "unix", "path"]
             .iter()
-            .map(|s| OsString::from(s))
+            .map(OsString::from)
             .collect();
 
         let parts: Vec<OsString> = path.parts().collect();
@@ -40,7 +40,7 @@ mod parts {
         let path = WindowsPath::new(r"C:\a\windows\path");
         let expected: Vec<OsString> = [r"C:\", "a", "windows", "path"]
             .iter()
-            .map(|s| OsString::from(s))
+            .map(OsString::from)
             .collect();
 
         let parts: Vec<OsString> = path.parts().collect();
@@ -53,7 +53,7 @@ mod parts {
         let path = WindowsPath::new(r"C:a\windows\path");
         let expected: Vec<OsString> = ["C:", "a", "windows", "path"]
             .iter()
-            .map(|s| OsString::from(s))
+            .map(OsString::from)
             .collect();
 
         let parts: Vec<OsString> = path.parts().collect();
@@ -66,7 +66,7 @@ mod parts {
         let path = WindowsPath::new(r"\a\windows\path");
         let expected: Vec<OsString> = [r"\", "a", "windows", "path"]
             .iter()
-            .map(|s| OsString::from(s))
+            .map(OsString::from)
             .collect();
 
         let parts: Vec<OsString> = path.parts().collect();
@@ -85,6 +85,209 @@ mod parts {
     }
 }
 
+mod file_name {
+    use super::*;
+    use std::ffi::OsStr;
+
+    #[test]
+    fn unixpath() {
+        let path = UnixPath::new("/a/unix/archive.tar.gz");
+
+        assert_eq!(path.file_name(), Some(OsStr::new("archive.tar.gz")));
+        assert_eq!(path.file_stem(), Some(OsStr::new("archive.tar")));
+        assert_eq!(path.extension(), Some(OsStr::new("gz")));
+        assert_eq!(path.suffixes(), vec![".tar", ".gz"]);
+    }
+
+    #[test]
+    fn unixpath_trailing_curdir() {
+        let path = UnixPath::new("a/unix/path.txt/.//");
+
+        assert_eq!(path.file_name(), Some(OsStr::new("path.txt")));
+        assert_eq!(path.extension(), Some(OsStr::new("txt")));
+    }
+
+    #[test]
+    fn unixpath_no_file_name() {
+        for p in &["/", "", ".", "a/..", "/a/\x00"] {
+            let path = UnixPath::new(p);
+            assert_eq!(path.file_name(), None);
+            assert_eq!(path.file_stem(), None);
+            assert_eq!(path.extension(), None);
+            assert!(path.suffixes().is_empty());
+        }
+    }
+
+    #[test]
+    fn unixpath_dot_files() {
+        let path = UnixPath::new("/home/.bashrc");
+        assert_eq!(path.file_stem(), Some(OsStr::new(".bashrc")));
+        assert_eq!(path.extension(), None);
+
+        let path = UnixPath::new("/home/.config.old");
+        assert_eq!(path.file_stem(), Some(OsStr::new(".config")));
+        assert_eq!(path.extension(), Some(OsStr::new("old")));
+
+        let path = UnixPath::new("/home/file.");
+        assert_eq!(path.file_stem(), Some(OsStr::new("file.")));
+        assert_eq!(path.extension(), None);
+    }
+
+    #[test]
+    fn unixpathbuf() {
+        let path = UnixPathBuf::from("hello.txt");
+        assert_eq!(path.extension(), Some(OsStr::new("txt")));
+    }
+
+    #[test]
+    fn windowspath() {
+        let path = WindowsPath::new(r"C:\a\windows\archive.tar.gz");
+
+        assert_eq!(path.file_name(), Some(OsStr::new("archive.tar.gz")));
+        assert_eq!(path.file_stem(), Some(OsStr::new("archive.tar")));
+        assert_eq!(path.extension(), Some(OsStr::new("gz")));
+        assert_eq!(path.suffixes(), vec![".tar", ".gz"]);
+    }
+
+    #[test]
+    fn windowspath_no_file_name() {
+        for p in &[r"C:\", "C:", r"\\server\share", r"a\..", r"C:\nul"] {
+            let path = WindowsPath::new(p);
+            assert_eq!(path.file_name(), None);
+            assert_eq!(path.extension(), None);
+            assert!(path.suffixes().is_empty());
+        }
+    }
+
+    #[test]
+    fn windowspath_invalid_suffix() {
+        let path = WindowsPath::new(r"C:\a.b .c");
+        assert_eq!(path.file_stem(), Some(OsStr::new("a.b ")));
+        assert_eq!(path.suffixes(), vec![".c"]);
+
+        let path = WindowsPath::new(r"C:\.gitignore");
+        assert_eq!(path.file_stem(), Some(OsStr::new(".gitignore")));
+        assert_eq!(path.extension(), None);
+    }
+
+    #[test]
+    fn windowspathbuf() {
+        let path = WindowsPathBuf::from(r"C:\hello.txt");
+        assert_eq!(path.extension(), Some(OsStr::new("txt")));
+    }
+}
+
+mod compare {
+    use super::*;
+    use std::ffi::OsStr;
+
+    #[test]
+    fn unixpath_starts_with() {
+        let path = UnixPath::new("/a/b/c");
+
+        assert!(path.starts_with("/a/b"));
+        assert!(path.starts_with("/a/b/"));
+        assert!(path.starts_with("/a//b/./"));
+        assert!(path.starts_with(""));
+        assert!(!path.starts_with("/a/bc"));
+        assert!(!path.starts_with("/a/b/c/d"));
+        assert!(!path.starts_with("a/b"));
+
+        let path = UnixPath::new("a//b");
+        assert!(path.starts_with("a/b"));
+    }
+
+    #[test]
+    fn unixpath_ends_with() {
+        let path = UnixPath::new("/a/b/c.txt");
+
+        assert!(path.ends_with("c.txt"));
+        assert!(path.ends_with("b//c.txt/"));
+        assert!(path.ends_with("/a/b/c.txt"));
+        assert!(!path.ends_with("/b/c.txt"));
+        assert!(!path.ends_with("txt"));
+    }
+
+    #[test]
+    fn unixpath_strip_prefix() {
+        let path = UnixPath::new("/a/b//./c/d");
+
+        assert_eq!(path.strip_prefix("/a/b"), Some(OsStr::new("c/d")));
+        assert_eq!(path.strip_prefix("/"), Some(OsStr::new("a/b//./c/d")));
+        assert_eq!(path.strip_prefix("/a/b/c/d"), Some(OsStr::new("")));
+        assert_eq!(path.strip_prefix("/a/bc"), None);
+        assert_eq!(path.strip_prefix("a"), None);
+    }
+
+    #[test]
+    fn unixpath_invalid() {
+        let path = UnixPath::new("/a/b\x00/c");
+
+        assert!(path.starts_with("/a"));
+        assert!(!path.starts_with("/a/b\x00"));
+        assert!(!path.ends_with("c"));
+    }
+
+    #[test]
+    fn unixpathbuf() {
+        let path = UnixPathBuf::from("/a/b/c");
+        assert!(path.starts_with("/a"));
+        assert!(path.ends_with("b/c"));
+        assert_eq!(path.strip_prefix("/a"), Some(OsStr::new("b/c")));
+    }
+
+    #[test]
+    fn windowspath_starts_with() {
+        let path = WindowsPath::new(r"c:\a\b");
+
+        assert!(path.starts_with(r"C:\"));
+        assert!(path.starts_with(r"C:/a"));
+        assert!(!path.starts_with(r"D:\"));
+        assert!(!path.starts_with(r"C:\A"));
+        assert!(!path.starts_with(r"\a"));
+
+        let path = WindowsPath::new(r"\\Server\Share\a");
+        assert!(path.starts_with(r"\\server\share"));
+        assert!(path.starts_with(r"//SERVER/SHARE/a"));
+        assert!(!path.starts_with(r"\\server\other"));
+
+        let path = WindowsPath::new(r"\\?\c:\a");
+        assert!(path.starts_with(r"\\?\C:\"));
+        assert!(!path.starts_with(r"C:\"));
+    }
+
+    #[test]
+    fn windowspath_ends_with() {
+        let path = WindowsPath::new(r"C:\a\b.txt");
+
+        assert!(path.ends_with("b.txt"));
+        assert!(path.ends_with(r"a/b.txt"));
+        assert!(path.ends_with(r"c:\a\b.txt"));
+        assert!(!path.ends_with(r"D:\a\b.txt"));
+    }
+
+    #[test]
+    fn windowspath_strip_prefix() {
+        let path = WindowsPath::new(r"C:\a\b\c");
+
+        assert_eq!(path.strip_prefix(r"c:\a"), Some(OsStr::new(r"b\c")));
+        assert_eq!(path.strip_prefix("C:"), Some(OsStr::new(r"\a\b\c")));
+        assert_eq!(path.strip_prefix(r"C:\a\b"), Some(OsStr::new("c")));
+        assert_eq!(path.strip_prefix(r"D:\a"), None);
+
+        let path = WindowsPath::new(r"\\?\C:\a\b");
+        assert_eq!(path.strip_prefix(r"\\?\c:\"), Some(OsStr::new(r"a\b")));
+    }
+
+    #[test]
+    fn windowspathbuf() {
+        let path = WindowsPathBuf::from(r"C:\a\b");
+        assert!(path.starts_with(r"c:\"));
+        assert!(path.ends_with(r"a\b"));
+        assert_eq!(path.strip_prefix(r"C:\"), Some(OsStr::new(r"a\b")));
+    }
+}
+
 // ===========================================================================
 //
 // ===========================================================================